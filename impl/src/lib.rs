@@ -1,9 +1,39 @@
 use proc_macro::TokenStream;
 use quote::quote;
+use syn::parse::{Parse, ParseStream};
 use syn::{parse_macro_input, DeriveInput};
 
+/// The arguments of `derive_stack_error`: an optional `code = Type`.
+struct Args {
+    code: syn::Type,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Ok(Self {
+                code: syn::parse_quote!(::stackerror::codes::ErrorCode),
+            });
+        }
+        let key: syn::Ident = input.parse()?;
+        if key != "code" {
+            return Err(syn::Error::new(key.span(), "expected `code = Type`"));
+        }
+        input.parse::<syn::Token![=]>()?;
+        Ok(Self {
+            code: input.parse()?,
+        })
+    }
+}
+
+/// Implements `ErrorStacks` and the standard error traits for a tuple struct
+/// by delegating to its first field. The field can be a `StackError`, or any
+/// type implementing `ErrorStacks` and `Default`, such as another derived
+/// error, so wrappers can be layered. The code type defaults to `ErrorCode`,
+/// and another one is set with `#[derive_stack_error(code = Type)]`.
 #[proc_macro_attribute]
-pub fn derive_stack_error(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn derive_stack_error(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let code = parse_macro_input!(attr as Args).code;
     let input = parse_macro_input!(item as DeriveInput);
     let name = &input.ident;
     let first_field_type = if let syn::Data::Struct(data) = &input.data {
@@ -16,85 +46,231 @@ pub fn derive_stack_error(_attr: TokenStream, item: TokenStream) -> TokenStream
         panic!("Expected a struct");
     };
 
+    // Paths are absolute, so that the expansion doesn't depend on what is
+    // imported where the macro is used
+    let stacks = quote!(<#first_field_type as ::stackerror::error::ErrorStacks<#code>>);
+
     let expanded = quote! {
         #input
 
         impl #name {
-            pub fn new() -> Self {
-                Self(#first_field_type::new())
+            #[track_caller]
+            pub fn new(error: impl ::std::fmt::Display + ::core::marker::Send + ::core::marker::Sync + 'static) -> Self {
+                Self::from_msg(error)
+            }
+
+            pub fn empty() -> Self {
+                Self(<#first_field_type as ::core::default::Default>::default())
+            }
+
+            #[track_caller]
+            pub fn from_msg(error: impl ::std::fmt::Display + ::core::marker::Send + ::core::marker::Sync + 'static) -> Self {
+                Self(#stacks::with_err_msg(
+                    <#first_field_type as ::core::default::Default>::default(),
+                    error,
+                ))
+            }
+
+            pub fn into_inner(self) -> #first_field_type {
+                self.0
+            }
+        }
+
+        impl ::core::default::Default for #name {
+            fn default() -> Self {
+                Self::empty()
             }
+        }
+
+        impl ::std::ops::Deref for #name {
+            type Target = #first_field_type;
 
-            pub fn from_msg(error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
-                Self(#first_field_type::from_msg(error))
+            fn deref(&self) -> &Self::Target {
+                &self.0
             }
         }
 
-        impl ErrorStacks<ErrorCode> for #name {
-            fn err_code(&self) -> Option<&ErrorCode> {
-                self.0.err_code()
+        impl ::core::convert::AsRef<#first_field_type> for #name {
+            fn as_ref(&self) -> &#first_field_type {
+                &self.0
             }
+        }
 
-            fn with_err_code(self, code: ErrorCode) -> Self {
-                Self(self.0.with_err_code(code))
+        impl ::stackerror::error::ErrorStacks<#code> for #name {
+            fn err_code(&self) -> ::core::option::Option<&#code> {
+                #stacks::err_code(&self.0)
+            }
+
+            fn with_err_code(self, code: #code) -> Self {
+                Self(#stacks::with_err_code(self.0, code))
             }
 
             fn with_no_err_code(self) -> Self {
-                Self(self.0.with_no_err_code())
+                Self(#stacks::with_no_err_code(self.0))
             }
 
-            fn err_uri(&self) -> Option<&str> {
-                self.0.err_uri()
+            fn err_uri(&self) -> ::core::option::Option<&str> {
+                #stacks::err_uri(&self.0)
             }
 
-            fn with_err_uri(self, uri: String) -> Self {
-                Self(self.0.with_err_uri(uri))
+            fn with_err_uri(self, uri: ::std::string::String) -> Self {
+                Self(#stacks::with_err_uri(self.0, uri))
             }
 
             fn with_no_err_uri(self) -> Self {
-                Self(self.0.with_no_err_uri())
+                Self(#stacks::with_no_err_uri(self.0))
+            }
+
+            fn err_fields(&self) -> &[(::std::string::String, ::std::string::String)] {
+                #stacks::err_fields(&self.0)
+            }
+
+            fn with_err_field(self, key: impl ::core::convert::Into<::std::string::String>, value: impl ::std::fmt::Display) -> Self {
+                Self(#stacks::with_err_field(self.0, key, value))
+            }
+
+            fn with_no_err_fields(self) -> Self {
+                Self(#stacks::with_no_err_fields(self.0))
+            }
+
+            fn err_spans(&self) -> &[::stackerror::span::ErrorSpan] {
+                #stacks::err_spans(&self.0)
+            }
+
+            fn with_err_span(self, source_name: impl ::core::convert::Into<::std::string::String>, range: ::std::ops::Range<usize>) -> Self {
+                Self(#stacks::with_err_span(self.0, source_name, range))
+            }
+
+            fn with_no_err_spans(self) -> Self {
+                Self(#stacks::with_no_err_spans(self.0))
+            }
+
+            fn err_position(&self) -> ::core::option::Option<&::stackerror::span::ErrorPosition> {
+                #stacks::err_position(&self.0)
+            }
+
+            fn with_err_position(self, offset: usize, line: usize, column: usize) -> Self {
+                Self(#stacks::with_err_position(self.0, offset, line, column))
+            }
+
+            fn with_no_err_position(self) -> Self {
+                Self(#stacks::with_no_err_position(self.0))
             }
 
-            fn with_err_msg(self, error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
-                Self(self.0.with_err_msg(error))
+            fn err_hint(&self) -> ::core::option::Option<&::stackerror::hint::RecoveryHint> {
+                #stacks::err_hint(&self.0)
+            }
+
+            fn with_err_hint(self, hint: ::stackerror::hint::RecoveryHint) -> Self {
+                Self(#stacks::with_err_hint(self.0, hint))
+            }
+
+            fn with_no_err_hint(self) -> Self {
+                Self(#stacks::with_no_err_hint(self.0))
+            }
+
+            fn err_origin(&self) -> ::core::option::Option<&::stackerror::origin::ErrorOrigin> {
+                #stacks::err_origin(&self.0)
+            }
+
+            fn with_err_origin(self, origin: ::stackerror::origin::ErrorOrigin) -> Self {
+                Self(#stacks::with_err_origin(self.0, origin))
+            }
+
+            fn with_no_err_origin(self) -> Self {
+                Self(#stacks::with_no_err_origin(self.0))
+            }
+
+            fn err_severity(&self) -> ::core::option::Option<&::stackerror::severity::Severity> {
+                #stacks::err_severity(&self.0)
+            }
+
+            fn with_err_severity(self, severity: ::stackerror::severity::Severity) -> Self {
+                Self(#stacks::with_err_severity(self.0, severity))
+            }
+
+            fn with_no_err_severity(self) -> Self {
+                Self(#stacks::with_no_err_severity(self.0))
+            }
+
+            fn err_elapsed(&self) -> ::core::option::Option<::std::time::Duration> {
+                #stacks::err_elapsed(&self.0)
+            }
+
+            fn with_err_elapsed(self, elapsed: ::std::time::Duration) -> Self {
+                Self(#stacks::with_err_elapsed(self.0, elapsed))
+            }
+
+            fn err_deadline(&self) -> ::core::option::Option<::std::time::Instant> {
+                #stacks::err_deadline(&self.0)
+            }
+
+            fn with_err_deadline(self, deadline: ::std::time::Instant) -> Self {
+                Self(#stacks::with_err_deadline(self.0, deadline))
+            }
+
+            fn with_err_msg(self, error: impl ::std::fmt::Display + ::core::marker::Send + ::core::marker::Sync + 'static) -> Self {
+                Self(#stacks::with_err_msg(self.0, error))
             }
 
             fn with_no_err_msg(self) -> Self {
-                Self(self.0.with_no_err_msg())
+                Self(#stacks::with_no_err_msg(self.0))
             }
 
             fn stack_err(self) -> Self {
-               Self(self.0.stack_err())
+               Self(#stacks::stack_err(self.0))
+            }
+
+            fn stack_err_msg(self, error: impl ::std::fmt::Display + ::core::marker::Send + ::core::marker::Sync + 'static) -> Self {
+               Self(#stacks::stack_err_msg(self.0, error))
             }
 
-            fn stack_err_msg(self, error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
-               Self(self.0.stack_err_msg(error))
+            fn with_err_location(self, location: &'static ::std::panic::Location<'static>) -> Self {
+                Self(#stacks::with_err_location(self.0, location))
             }
         }
 
-        impl std::fmt::Display for #name {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                std::fmt::Display::fmt(&self.0, f)
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&self.0, f)
             }
         }
 
-        impl std::fmt::Debug for #name {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                std::fmt::Debug::fmt(&self.0, f)
+        impl ::std::fmt::Debug for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(&self.0, f)
             }
         }
 
-        impl std::error::Error for #name {
-            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-                self.0.source()
+        impl ::std::error::Error for #name {
+            fn source(&self) -> ::core::option::Option<&(dyn ::std::error::Error + 'static)> {
+                ::std::error::Error::source(&self.0)
             }
         }
 
-        impl<E> From<E> for #name
+        impl<E> ::core::convert::From<E> for #name
         where
-            StackError: From<E>,
+            #first_field_type: ::core::convert::From<E>,
         {
             fn from(err: E) -> Self {
-                Self(StackError::from(err))
+                Self(<#first_field_type as ::core::convert::From<E>>::from(err))
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(IntoStackError)]
+pub fn derive_into_stack_error(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let expanded = quote! {
+        impl #impl_generics ::core::convert::From<#name #ty_generics> for ::stackerror::error::StackError #where_clause {
+            fn from(error: #name #ty_generics) -> Self {
+                ::stackerror::error::StackError::from_err(error)
             }
         }
     };
@@ -0,0 +1,176 @@
+//! Provides the [`StackErrorBuilder`] for declaring errors in one expression.
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::hint::RecoveryHint;
+use crate::origin::ErrorOrigin;
+use crate::severity::Severity;
+
+/// Fluent builder for a [`StackError`] with any number of frames.
+///
+/// Each call to [`StackErrorBuilder::frame`] stacks a new frame on the
+/// previous ones. Codes, URIs and fields apply to the most recent frame. This
+/// is mostly useful in tests and fixtures, where expected errors can be
+/// declared compactly:
+///
+/// ```rust
+/// let expected = StackErrorBuilder::new()
+///     .frame("failed to open file")
+///     .code(ErrorCode::IoNotFound)
+///     .field("path", "config.toml")
+///     .frame("failed to load config")
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct StackErrorBuilder {
+    error: Option<StackError>,
+}
+
+impl StackErrorBuilder {
+    /// Creates a new builder without any frames.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stacks a new frame with the given message.
+    #[track_caller]
+    pub fn frame(self, message: impl std::fmt::Display + Send + Sync + 'static) -> Self {
+        let error = match self.error {
+            Some(error) => error.stack_err_msg(message),
+            None => StackError::from_msg(message),
+        };
+        Self { error: Some(error) }
+    }
+
+    /// Sets the error code on the current frame.
+    pub fn code(self, code: ErrorCode) -> Self {
+        self.map(|e| e.with_err_code(code))
+    }
+
+    /// Sets the error URI on the current frame.
+    pub fn uri(self, uri: impl Into<String>) -> Self {
+        self.map(|e| e.with_err_uri(uri.into()))
+    }
+
+    /// Attaches a key-value field to the current frame.
+    pub fn field(self, key: impl Into<String>, value: impl std::fmt::Display) -> Self {
+        self.map(|e| e.with_err_field(key, value))
+    }
+
+    /// Attaches a source span to the current frame.
+    pub fn span(self, source_name: impl Into<String>, range: std::ops::Range<usize>) -> Self {
+        self.map(|e| e.with_err_span(source_name, range))
+    }
+
+    /// Sets the recovery hint on the current frame.
+    pub fn hint(self, hint: RecoveryHint) -> Self {
+        self.map(|e| e.with_err_hint(hint))
+    }
+
+    /// Sets the origin on the current frame.
+    pub fn origin(self, origin: ErrorOrigin) -> Self {
+        self.map(|e| e.with_err_origin(origin))
+    }
+
+    /// Sets the severity on the current frame.
+    pub fn severity(self, severity: Severity) -> Self {
+        self.map(|e| e.with_err_severity(severity))
+    }
+
+    /// Sets the input position on the current frame.
+    pub fn position(self, offset: usize, line: usize, column: usize) -> Self {
+        self.map(|e| e.with_err_position(offset, line, column))
+    }
+
+    /// Builds the error. An empty error is returned if no frames were added.
+    pub fn build(self) -> StackError {
+        self.error.unwrap_or_default()
+    }
+
+    fn map(self, f: impl FnOnce(StackError) -> StackError) -> Self {
+        Self {
+            error: Some(f(self.error.unwrap_or_default())),
+        }
+    }
+}
+
+impl StackError {
+    /// Creates a new [`StackErrorBuilder`] without any frames.
+    pub fn build() -> StackErrorBuilder {
+        StackErrorBuilder::new()
+    }
+
+    /// Creates a new StackError with a message, error code and URI.
+    #[track_caller]
+    pub fn with_err(
+        error: impl std::fmt::Display + Send + Sync + 'static,
+        code: ErrorCode,
+        uri: impl Into<String>,
+    ) -> Self {
+        Self::from_msg(error)
+            .with_err_code(code)
+            .with_err_uri(uri.into())
+    }
+}
+
+impl From<StackErrorBuilder> for StackError {
+    fn from(builder: StackErrorBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_builds_empty() {
+        let error = StackErrorBuilder::new().build();
+        assert_eq!(format!("{:?}", error), "");
+        assert_eq!(error.err_code(), None);
+    }
+
+    #[test]
+    fn test_builder_stacks_frames() {
+        let error = StackErrorBuilder::new()
+            .frame("Base error")
+            .code(ErrorCode::IoNotFound)
+            .uri("https://example.com/base")
+            .field("path", "config.toml")
+            .frame("Stacked error")
+            .field("attempt", 2)
+            .build();
+        assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+        assert_eq!(error.err_location().unwrap().file(), "src/builder.rs");
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+        assert_eq!(error.err_uri(), Some("https://example.com/base"));
+        assert_eq!(
+            error.err_fields(),
+            &[("attempt".to_string(), "2".to_string())]
+        );
+        let source = std::error::Error::source(&error)
+            .and_then(|e| e.downcast_ref::<StackError>())
+            .unwrap();
+        assert_eq!(
+            source.err_fields(),
+            &[("path".to_string(), "config.toml".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_with_err_sets_all() {
+        let error = StackError::with_err(
+            "Test error",
+            ErrorCode::HttpConflict,
+            "https://example.com/conflict",
+        );
+        let built = StackError::build()
+            .frame("Test error")
+            .code(ErrorCode::HttpConflict)
+            .uri("https://example.com/conflict")
+            .build();
+        assert_eq!(format!("{:?}", error), format!("{:?}", built));
+        assert_eq!(error.err_code(), built.err_code());
+        assert_eq!(error.err_uri(), built.err_uri());
+    }
+}
@@ -0,0 +1,125 @@
+//! Provides a catalog of the errors defined with
+//! [`register_error!`](crate::register_error), which can be enumerated at
+//! runtime, e.g. for a docs endpoint or to test that every error is
+//! documented.
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+#[doc(hidden)]
+pub use inventory as __inventory;
+
+/// A statically defined error, registered with
+/// [`register_error!`](crate::register_error).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCatalogEntry {
+    /// The name of the error.
+    pub name: &'static str,
+    /// The code of the error.
+    pub code: Option<ErrorCode>,
+    /// The URI of the error.
+    pub uri: Option<&'static str>,
+    /// The message template of the error. `{key}` placeholders are replaced
+    /// by the arguments of [`ErrorCatalogEntry::error_with`].
+    pub template: &'static str,
+}
+
+inventory::collect!(ErrorCatalogEntry);
+
+impl ErrorCatalogEntry {
+    /// Creates an error with the template as message, and the code and URI.
+    pub fn error(&self) -> StackError {
+        self.error_with(std::iter::empty::<(&str, &str)>())
+    }
+
+    /// Creates an error as [`ErrorCatalogEntry::error`], with the `{key}`
+    /// placeholders of the template replaced by the arguments. The arguments
+    /// are also set as fields.
+    pub fn error_with<K, V>(&self, args: impl IntoIterator<Item = (K, V)>) -> StackError
+    where
+        K: AsRef<str>,
+        V: std::fmt::Display,
+    {
+        let mut message = self.template.to_string();
+        let mut fields = Vec::new();
+        for (key, value) in args {
+            let value = value.to_string();
+            message = message.replace(&format!("{{{}}}", key.as_ref()), &value);
+            fields.push((key.as_ref().to_string(), value));
+        }
+        let mut error = StackError::from_msg(message);
+        if let Some(code) = self.code {
+            error = error.with_err_code(code);
+        }
+        if let Some(uri) = self.uri {
+            error = error.with_err_uri(uri.to_string());
+        }
+        fields.into_iter().fold(error, |error, (key, value)| {
+            error.with_err_field(key, value)
+        })
+    }
+}
+
+/// Get all the registered errors, in all crates linked into the binary,
+/// sorted by name.
+pub fn error_catalog() -> Vec<&'static ErrorCatalogEntry> {
+    let mut entries: Vec<_> = inventory::iter::<ErrorCatalogEntry>.into_iter().collect();
+    entries.sort_by_key(|entry| entry.name);
+    entries
+}
+
+/// Defines a constant [`ErrorCatalogEntry`] and registers it in the
+/// [`error_catalog`]. The code and URI are optional:
+///
+/// ```rust
+/// register_error!(USER_NOT_FOUND, "user {id} not found", code: ErrorCode::HttpNotFound);
+///
+/// return Err(USER_NOT_FOUND.error_with([("id", id)]));
+/// ```
+#[macro_export]
+macro_rules! register_error {
+    ($vis:vis $name:ident, $template:expr $(, code: $code:expr)? $(, uri: $uri:expr)? $(,)?) => {
+        $vis const $name: $crate::catalog::ErrorCatalogEntry = $crate::catalog::ErrorCatalogEntry {
+            name: stringify!($name),
+            code: $crate::register_error!(@opt $($code)?),
+            uri: $crate::register_error!(@opt $($uri)?),
+            template: $template,
+        };
+        $crate::catalog::__inventory::submit! { $name }
+    };
+    (@opt) => { None };
+    (@opt $value:expr) => { Some($value) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    register_error!(
+        CATALOG_USER_NOT_FOUND,
+        "user {id} not found",
+        code: ErrorCode::HttpNotFound,
+        uri: "error://stackerror/user_not_found",
+    );
+    register_error!(CATALOG_INTERNAL, "internal error");
+
+    #[test]
+    fn test_catalog_lists_errors() {
+        let names: Vec<_> = error_catalog()
+            .iter()
+            .map(|entry| entry.name)
+            .filter(|name| name.starts_with("CATALOG_"))
+            .collect();
+        assert_eq!(names, ["CATALOG_INTERNAL", "CATALOG_USER_NOT_FOUND"]);
+    }
+
+    #[test]
+    fn test_catalog_entry_builds_error() {
+        let error = CATALOG_USER_NOT_FOUND.error_with([("id", 42)]);
+        assert_eq!(error.to_string(), "user 42 not found");
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpNotFound));
+        assert_eq!(error.err_uri(), Some("error://stackerror/user_not_found"));
+        assert_eq!(error.err_fields(), &[("id".to_string(), "42".to_string())]);
+        assert_eq!(CATALOG_INTERNAL.error().err_code(), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Provides the [`run`] harness for command line programs using `clap`, and
+//! conversions between `clap` errors and `StackError`.
+
+use std::ffi::OsString;
+use std::process::ExitCode;
+
+use clap::error::ErrorKind;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+/// Parse the arguments of the process and run `main` with them.
+///
+/// Invalid arguments are reported by `clap`, and `--help` and `--version` are
+/// handled as usual. If `main` fails, its error is printed as a report on
+/// standard error, and the exit code is mapped from the error code as by
+/// [`StackError::exit_code`]. A [crash report](crate::crash) is also written
+/// if they are enabled:
+///
+/// ```rust
+/// #[derive(clap::Parser)]
+/// struct Args {
+///     path: PathBuf,
+/// }
+///
+/// fn main() -> ExitCode {
+///     stackerror::cli::run(|args: Args| {
+///         let data = std::fs::read(&args.path).stack_err_msg("reading input")?;
+///         ...
+///     })
+/// }
+/// ```
+pub fn run<A: clap::Parser>(main: impl FnOnce(A) -> Result<(), StackError>) -> ExitCode {
+    run_from(std::env::args_os(), main)
+}
+
+/// Run as [`run`], with the given arguments instead of those of the process.
+/// The first argument is the program name.
+pub fn run_from<A, I, T>(args: I, main: impl FnOnce(A) -> Result<(), StackError>) -> ExitCode
+where
+    A: clap::Parser,
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args = match A::try_parse_from(args) {
+        Ok(args) => args,
+        Err(error) => {
+            let _ = error.print();
+            return ExitCode::from(error.exit_code() as u8);
+        }
+    };
+    match main(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprint!("error:\n{}", error.render_report());
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            crate::crash::report_crash(&error);
+            error.exit_code()
+        }
+    }
+}
+
+/// Argument errors have the `IoInvalidInput` code, which maps to the
+/// `EX_USAGE` exit status.
+impl From<clap::Error> for StackError {
+    fn from(error: clap::Error) -> Self {
+        StackError::from_err(error).with_err_code(ErrorCode::IoInvalidInput)
+    }
+}
+
+/// Converts to a `clap` error rendered with the `Display` output, e.g. to
+/// report an invalid combination of arguments found after parsing in the
+/// same style as parsing errors.
+impl From<StackError> for clap::Error {
+    fn from(error: StackError) -> Self {
+        let kind = match error.err_code() {
+            Some(ErrorCode::IoInvalidInput | ErrorCode::RuntimeInvalidValue) => {
+                ErrorKind::ValueValidation
+            }
+            _ => ErrorKind::Io,
+        };
+        clap::Error::raw(kind, format!("{error}\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(clap::Parser)]
+    struct Args {
+        #[arg(long)]
+        count: u32,
+    }
+
+    fn app(args: Args) -> Result<(), StackError> {
+        match args.count {
+            0 => Err(StackError::from_msg("count is zero").with_err_code(ErrorCode::IoNotFound)),
+            _ => Ok(()),
+        }
+    }
+
+    #[test]
+    fn test_run_exit_codes() {
+        assert_eq!(run_from(["app", "--count", "1"], app), ExitCode::SUCCESS);
+        assert_eq!(run_from(["app", "--count", "0"], app), ExitCode::from(66));
+        assert_eq!(run_from(["app", "--count", "x"], app), ExitCode::from(2));
+    }
+
+    #[test]
+    fn test_clap_error_conversions() {
+        let error = <Args as clap::Parser>::try_parse_from(["app"])
+            .err()
+            .unwrap();
+        let error = StackError::from(error);
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidInput));
+        assert!(error.downcast_ref::<clap::Error>().is_some());
+
+        let error = clap::Error::from(StackError::invalid_value("count must be even"));
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        assert_eq!(error.to_string(), "error: count must be even\n");
+    }
+}
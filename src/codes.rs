@@ -1,5 +1,13 @@
 //! Provides the [`ErrorCode`] enum.
+//!
+//! The codes and their HTTP status and exit status tables only use `core`, so
+//! they can be reused by HTTP stacks without `std`, with the `std` feature
+//! disabled. Only the conversions to
+//! and from `std::io::ErrorKind` require the `io` feature. The conversions to
+//! and from `embedded_io::ErrorKind` don't require `std`, so firmware can
+//! classify errors with the same codes as hosted builds.
 
+#[cfg(feature = "io")]
 use std::io::ErrorKind;
 
 /// Error handling codes.
@@ -7,11 +15,15 @@ use std::io::ErrorKind;
 /// Provides runtime information that the caller can use to bypass faulty
 /// resources or reformulate an operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ErrorCode {
     RuntimeInvalidValue,
     RuntimeInvalidIndex,
     RuntimeInvalidKey,
     RuntimeNotImplemented,
+    RuntimePanic,
+    RuntimeProcessFailed,
     // HTTP 4xx
     HttpBadRequest,
     HttpUnauthorized,
@@ -78,6 +90,146 @@ pub enum ErrorCode {
 }
 
 impl ErrorCode {
+    /// All the error codes.
+    pub const ALL: [ErrorCode; 66] = [
+        Self::RuntimeInvalidValue,
+        Self::RuntimeInvalidIndex,
+        Self::RuntimeInvalidKey,
+        Self::RuntimeNotImplemented,
+        Self::RuntimePanic,
+        Self::RuntimeProcessFailed,
+        Self::HttpBadRequest,
+        Self::HttpUnauthorized,
+        Self::HttpPaymentRequired,
+        Self::HttpForbidden,
+        Self::HttpNotFound,
+        Self::HttpMethodNotAllowed,
+        Self::HttpNotAcceptable,
+        Self::HttpProxyAuthenticationRequired,
+        Self::HttpRequestTimeout,
+        Self::HttpConflict,
+        Self::HttpGone,
+        Self::HttpLengthRequired,
+        Self::HttpPreconditionFailed,
+        Self::HttpPayloadTooLarge,
+        Self::HttpUriTooLong,
+        Self::HttpUnsupportedMediaType,
+        Self::HttpRangeNotSatisfiable,
+        Self::HttpExpectationFailed,
+        Self::HttpImATeapot,
+        Self::HttpMisdirectedRequest,
+        Self::HttpUnprocessableEntity,
+        Self::HttpLocked,
+        Self::HttpFailedDependency,
+        Self::HttpTooEarly,
+        Self::HttpUpgradeRequired,
+        Self::HttpPreconditionRequired,
+        Self::HttpTooManyRequests,
+        Self::HttpRequestHeaderFieldsTooLarge,
+        Self::HttpUnavailableForLegalReasons,
+        Self::HttpInternalServerError,
+        Self::HttpNotImplemented,
+        Self::HttpBadGateway,
+        Self::HttpServiceUnavailable,
+        Self::HttpGatewayTimeout,
+        Self::HttpHttpVersionNotSupported,
+        Self::HttpVariantAlsoNegotiates,
+        Self::HttpInsufficientStorage,
+        Self::HttpLoopDetected,
+        Self::HttpNotExtended,
+        Self::HttpNetworkAuthenticationRequired,
+        Self::IoNotFound,
+        Self::IoPermissionDenied,
+        Self::IoConnectionRefused,
+        Self::IoConnectionReset,
+        Self::IoConnectionAborted,
+        Self::IoNotConnected,
+        Self::IoAddrInUse,
+        Self::IoAddrNotAvailable,
+        Self::IoBrokenPipe,
+        Self::IoAlreadyExists,
+        Self::IoWouldBlock,
+        Self::IoInvalidInput,
+        Self::IoInvalidData,
+        Self::IoTimedOut,
+        Self::IoWriteZero,
+        Self::IoInterrupted,
+        Self::IoUnsupported,
+        Self::IoUnexpectedEof,
+        Self::IoOutOfMemory,
+        Self::IoOther,
+    ];
+
+    /// The names of the codes in [`ALL`](Self::ALL), as printed by `Debug`.
+    const NAMES: [&'static str; 66] = [
+        "RuntimeInvalidValue",
+        "RuntimeInvalidIndex",
+        "RuntimeInvalidKey",
+        "RuntimeNotImplemented",
+        "RuntimePanic",
+        "RuntimeProcessFailed",
+        "HttpBadRequest",
+        "HttpUnauthorized",
+        "HttpPaymentRequired",
+        "HttpForbidden",
+        "HttpNotFound",
+        "HttpMethodNotAllowed",
+        "HttpNotAcceptable",
+        "HttpProxyAuthenticationRequired",
+        "HttpRequestTimeout",
+        "HttpConflict",
+        "HttpGone",
+        "HttpLengthRequired",
+        "HttpPreconditionFailed",
+        "HttpPayloadTooLarge",
+        "HttpUriTooLong",
+        "HttpUnsupportedMediaType",
+        "HttpRangeNotSatisfiable",
+        "HttpExpectationFailed",
+        "HttpImATeapot",
+        "HttpMisdirectedRequest",
+        "HttpUnprocessableEntity",
+        "HttpLocked",
+        "HttpFailedDependency",
+        "HttpTooEarly",
+        "HttpUpgradeRequired",
+        "HttpPreconditionRequired",
+        "HttpTooManyRequests",
+        "HttpRequestHeaderFieldsTooLarge",
+        "HttpUnavailableForLegalReasons",
+        "HttpInternalServerError",
+        "HttpNotImplemented",
+        "HttpBadGateway",
+        "HttpServiceUnavailable",
+        "HttpGatewayTimeout",
+        "HttpHttpVersionNotSupported",
+        "HttpVariantAlsoNegotiates",
+        "HttpInsufficientStorage",
+        "HttpLoopDetected",
+        "HttpNotExtended",
+        "HttpNetworkAuthenticationRequired",
+        "IoNotFound",
+        "IoPermissionDenied",
+        "IoConnectionRefused",
+        "IoConnectionReset",
+        "IoConnectionAborted",
+        "IoNotConnected",
+        "IoAddrInUse",
+        "IoAddrNotAvailable",
+        "IoBrokenPipe",
+        "IoAlreadyExists",
+        "IoWouldBlock",
+        "IoInvalidInput",
+        "IoInvalidData",
+        "IoTimedOut",
+        "IoWriteZero",
+        "IoInterrupted",
+        "IoUnsupported",
+        "IoUnexpectedEof",
+        "IoOutOfMemory",
+        "IoOther",
+    ];
+
     /// Construct from an HTTP error code value.
     pub fn from_http_value(value: u16) -> Option<Self> {
         Some(match value {
@@ -177,6 +329,7 @@ impl ErrorCode {
     }
 
     /// Construct from an IO error kind.
+    #[cfg(feature = "io")]
     pub fn from_io_kind(kind: ErrorKind) -> Option<Self> {
         Some(match kind {
             ErrorKind::NotFound => Self::IoNotFound,
@@ -204,6 +357,7 @@ impl ErrorCode {
     }
 
     /// Convert to its corresponding `std::io::ErrorKind`, if any.
+    #[cfg(feature = "io")]
     pub fn to_io_kind(self) -> Option<ErrorKind> {
         let kind = match self {
             Self::IoNotFound => ErrorKind::NotFound,
@@ -230,12 +384,200 @@ impl ErrorCode {
         };
         Some(kind)
     }
+
+    /// Construct from an `embedded-io` error kind.
+    #[cfg(feature = "embedded-io")]
+    pub fn from_embedded_io_kind(kind: embedded_io::ErrorKind) -> Option<Self> {
+        Some(match kind {
+            embedded_io::ErrorKind::NotFound => Self::IoNotFound,
+            embedded_io::ErrorKind::PermissionDenied => Self::IoPermissionDenied,
+            embedded_io::ErrorKind::ConnectionRefused => Self::IoConnectionRefused,
+            embedded_io::ErrorKind::ConnectionReset => Self::IoConnectionReset,
+            embedded_io::ErrorKind::ConnectionAborted => Self::IoConnectionAborted,
+            embedded_io::ErrorKind::NotConnected => Self::IoNotConnected,
+            embedded_io::ErrorKind::AddrInUse => Self::IoAddrInUse,
+            embedded_io::ErrorKind::AddrNotAvailable => Self::IoAddrNotAvailable,
+            embedded_io::ErrorKind::BrokenPipe => Self::IoBrokenPipe,
+            embedded_io::ErrorKind::AlreadyExists => Self::IoAlreadyExists,
+            embedded_io::ErrorKind::InvalidInput => Self::IoInvalidInput,
+            embedded_io::ErrorKind::InvalidData => Self::IoInvalidData,
+            embedded_io::ErrorKind::TimedOut => Self::IoTimedOut,
+            embedded_io::ErrorKind::Interrupted => Self::IoInterrupted,
+            embedded_io::ErrorKind::Unsupported => Self::IoUnsupported,
+            embedded_io::ErrorKind::OutOfMemory => Self::IoOutOfMemory,
+            embedded_io::ErrorKind::WriteZero => Self::IoWriteZero,
+            embedded_io::ErrorKind::Other => Self::IoOther,
+            _ => return None,
+        })
+    }
+
+    /// Convert to its corresponding `embedded-io` error kind, if any.
+    #[cfg(feature = "embedded-io")]
+    pub fn to_embedded_io_kind(self) -> Option<embedded_io::ErrorKind> {
+        let kind = match self {
+            Self::IoNotFound => embedded_io::ErrorKind::NotFound,
+            Self::IoPermissionDenied => embedded_io::ErrorKind::PermissionDenied,
+            Self::IoConnectionRefused => embedded_io::ErrorKind::ConnectionRefused,
+            Self::IoConnectionReset => embedded_io::ErrorKind::ConnectionReset,
+            Self::IoConnectionAborted => embedded_io::ErrorKind::ConnectionAborted,
+            Self::IoNotConnected => embedded_io::ErrorKind::NotConnected,
+            Self::IoAddrInUse => embedded_io::ErrorKind::AddrInUse,
+            Self::IoAddrNotAvailable => embedded_io::ErrorKind::AddrNotAvailable,
+            Self::IoBrokenPipe => embedded_io::ErrorKind::BrokenPipe,
+            Self::IoAlreadyExists => embedded_io::ErrorKind::AlreadyExists,
+            Self::IoInvalidInput => embedded_io::ErrorKind::InvalidInput,
+            Self::IoInvalidData => embedded_io::ErrorKind::InvalidData,
+            Self::IoTimedOut => embedded_io::ErrorKind::TimedOut,
+            Self::IoInterrupted => embedded_io::ErrorKind::Interrupted,
+            Self::IoUnsupported => embedded_io::ErrorKind::Unsupported,
+            Self::IoOutOfMemory => embedded_io::ErrorKind::OutOfMemory,
+            Self::IoWriteZero => embedded_io::ErrorKind::WriteZero,
+            Self::IoOther => embedded_io::ErrorKind::Other,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Get the BSD `sysexits.h` exit status for the code, for CLI tools whose
+    /// exit statuses are consumed by scripts.
+    pub fn sysexit(self) -> u8 {
+        match self {
+            // EX_DATAERR
+            Self::RuntimeInvalidValue
+            | Self::RuntimeInvalidIndex
+            | Self::RuntimeInvalidKey
+            | Self::IoInvalidData
+            | Self::HttpUnprocessableEntity => 65,
+            // EX_USAGE
+            Self::IoInvalidInput | Self::HttpBadRequest => 64,
+            // EX_NOINPUT
+            Self::IoNotFound | Self::IoUnexpectedEof => 66,
+            // EX_UNAVAILABLE
+            Self::IoConnectionRefused
+            | Self::IoConnectionReset
+            | Self::IoConnectionAborted
+            | Self::IoNotConnected
+            | Self::IoAddrNotAvailable
+            | Self::IoUnsupported
+            | Self::HttpNotFound
+            | Self::HttpGone
+            | Self::HttpNotImplemented
+            | Self::HttpBadGateway
+            | Self::HttpServiceUnavailable
+            | Self::HttpInternalServerError => 69,
+            // EX_SOFTWARE
+            Self::RuntimeNotImplemented | Self::RuntimePanic | Self::RuntimeProcessFailed => 70,
+            // EX_OSERR
+            Self::IoOutOfMemory | Self::IoAddrInUse => 71,
+            // EX_CANTCREAT
+            Self::IoAlreadyExists => 73,
+            // EX_IOERR
+            Self::IoBrokenPipe | Self::IoWriteZero | Self::IoOther => 74,
+            // EX_TEMPFAIL
+            Self::IoTimedOut
+            | Self::IoWouldBlock
+            | Self::IoInterrupted
+            | Self::HttpRequestTimeout
+            | Self::HttpTooManyRequests
+            | Self::HttpGatewayTimeout
+            | Self::HttpTooEarly => 75,
+            // EX_NOPERM
+            Self::IoPermissionDenied
+            | Self::HttpUnauthorized
+            | Self::HttpForbidden
+            | Self::HttpProxyAuthenticationRequired
+            | Self::HttpNetworkAuthenticationRequired
+            | Self::HttpUnavailableForLegalReasons => 77,
+            // EX_PROTOCOL
+            _ => 76,
+        }
+    }
+
+    /// Check if the code is for a transient failure, which can succeed if the
+    /// operation is retried unchanged, such as timeouts, dropped connections
+    /// and overloaded services.
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            Self::IoConnectionRefused
+                | Self::IoConnectionReset
+                | Self::IoConnectionAborted
+                | Self::IoNotConnected
+                | Self::IoBrokenPipe
+                | Self::IoWouldBlock
+                | Self::IoTimedOut
+                | Self::IoInterrupted
+                | Self::HttpRequestTimeout
+                | Self::HttpTooEarly
+                | Self::HttpTooManyRequests
+                | Self::HttpBadGateway
+                | Self::HttpServiceUnavailable
+                | Self::HttpGatewayTimeout
+        )
+    }
+}
+
+/// Parses an error code from its variant name, as printed by `Debug`.
+impl core::str::FromStr for ErrorCode {
+    type Err = UnknownErrorCode;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .position(|&code_name| code_name == name)
+            .map(|index| Self::ALL[index])
+            .ok_or(UnknownErrorCode)
+    }
+}
+
+/// Converts from an HTTP error code value.
+impl TryFrom<u16> for ErrorCode {
+    type Error = UnknownErrorCode;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Self::from_http_value(value).ok_or(UnknownErrorCode)
+    }
+}
+
+/// Error returned when parsing an unknown error code name or value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownErrorCode;
+
+impl core::fmt::Display for UnknownErrorCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "unknown error code")
+    }
+}
+
+impl core::error::Error for UnknownErrorCode {}
+
+/// Converts from an HTTP error status. Fails for statuses which aren't
+/// errors.
+#[cfg(feature = "http")]
+impl TryFrom<http::StatusCode> for ErrorCode {
+    type Error = UnknownErrorCode;
+
+    fn try_from(status: http::StatusCode) -> Result<Self, Self::Error> {
+        ErrorCode::try_from(status.as_u16())
+    }
+}
+
+/// Converts to an HTTP status. Fails, returning the code, for codes which
+/// aren't HTTP codes.
+#[cfg(feature = "http")]
+impl TryFrom<ErrorCode> for http::StatusCode {
+    type Error = ErrorCode;
+
+    fn try_from(code: ErrorCode) -> Result<Self, Self::Error> {
+        ErrorCode::to_http_value(code)
+            .and_then(|value| http::StatusCode::from_u16(value).ok())
+            .ok_or(code)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::ErrorKind;
 
     /// A few well-chosen HTTP codes should round-trip.
     #[test]
@@ -268,6 +610,7 @@ mod tests {
     }
 
     /// Typical IO kinds should also round-trip.
+    #[cfg(feature = "io")]
     #[test]
     fn io_roundtrip() {
         let samples = [
@@ -283,4 +626,75 @@ mod tests {
             assert_eq!(variant.to_io_kind(), Some(kind));
         }
     }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn embedded_io_roundtrip() {
+        let samples = [
+            (embedded_io::ErrorKind::NotFound, ErrorCode::IoNotFound),
+            (embedded_io::ErrorKind::TimedOut, ErrorCode::IoTimedOut),
+            (embedded_io::ErrorKind::WriteZero, ErrorCode::IoWriteZero),
+        ];
+
+        for (kind, variant) in samples {
+            assert_eq!(ErrorCode::from_embedded_io_kind(kind), Some(variant));
+            assert_eq!(variant.to_embedded_io_kind(), Some(kind));
+        }
+        assert_eq!(ErrorCode::IoUnexpectedEof.to_embedded_io_kind(), None);
+    }
+
+    #[test]
+    fn http_try_from() {
+        assert_eq!(ErrorCode::try_from(404), Ok(ErrorCode::HttpNotFound));
+        assert_eq!(ErrorCode::try_from(200), Err(UnknownErrorCode));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn http_status_code_conversions() {
+        assert_eq!(
+            ErrorCode::try_from(http::StatusCode::CONFLICT),
+            Ok(ErrorCode::HttpConflict)
+        );
+        assert_eq!(
+            ErrorCode::try_from(http::StatusCode::OK),
+            Err(UnknownErrorCode)
+        );
+        assert_eq!(
+            http::StatusCode::try_from(ErrorCode::HttpConflict),
+            Ok(http::StatusCode::CONFLICT)
+        );
+        assert_eq!(
+            http::StatusCode::try_from(ErrorCode::IoNotFound),
+            Err(ErrorCode::IoNotFound)
+        );
+    }
+
+    /// Every code should map to a sysexits.h status.
+    #[test]
+    fn sysexit_ranges() {
+        for code in ErrorCode::ALL {
+            assert!((64..=78).contains(&code.sysexit()), "{code:?}");
+        }
+        assert_eq!(ErrorCode::IoPermissionDenied.sysexit(), 77);
+        assert_eq!(ErrorCode::HttpTooManyRequests.sysexit(), 75);
+    }
+
+    #[test]
+    fn transient_codes() {
+        assert!(ErrorCode::IoTimedOut.is_transient());
+        assert!(ErrorCode::HttpServiceUnavailable.is_transient());
+        assert!(!ErrorCode::HttpNotFound.is_transient());
+        assert!(!ErrorCode::RuntimeInvalidValue.is_transient());
+    }
+
+    /// Every code should parse from its name.
+    #[test]
+    fn name_roundtrip() {
+        for (code, name) in ErrorCode::ALL.into_iter().zip(ErrorCode::NAMES) {
+            assert_eq!(format!("{code:?}"), name);
+            assert_eq!(name.parse(), Ok(code));
+        }
+        assert_eq!("NotACode".parse::<ErrorCode>(), Err(UnknownErrorCode));
+    }
 }
@@ -0,0 +1,118 @@
+//! Provides a `codespan-reporting` backend for rendering errors with their
+//! source spans.
+
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::files::SimpleFiles;
+use codespan_reporting::term;
+use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+
+use crate::error::{ErrorStacks, StackError};
+
+/// Convert an error into a diagnostic.
+///
+/// The top frame provides the diagnostic message, and the other frames are
+/// added as notes. Spans become labels with their frame's message: the top
+/// frame's spans are primary labels, and the others are secondary. The
+/// `file_id` function maps a span's source name to a file ID, and spans
+/// without a file ID are skipped.
+pub fn to_diagnostic<FileId>(
+    error: &StackError,
+    mut file_id: impl FnMut(&str) -> Option<FileId>,
+) -> Diagnostic<FileId> {
+    let mut diagnostic = Diagnostic::error().with_message(error);
+    if let Some(code) = error.err_code() {
+        diagnostic = diagnostic.with_code(format!("{code:?}"));
+    }
+    let frames = std::iter::successors(Some(error), |e| {
+        std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+    });
+    for (idx, frame) in frames.enumerate() {
+        let message = frame.frame_msg().to_string();
+        for span in frame.err_spans() {
+            let Some(id) = file_id(&span.source_name) else {
+                continue;
+            };
+            let label = if idx == 0 {
+                Label::primary(id, span.range.clone())
+            } else {
+                Label::secondary(id, span.range.clone())
+            };
+            diagnostic = diagnostic.with_label(label.with_message(&message));
+        }
+        if idx > 0 && !message.is_empty() {
+            diagnostic = diagnostic.with_note(message);
+        }
+    }
+    diagnostic
+}
+
+/// Build the file database and diagnostic for `sources`, given as pairs of
+/// source names and contents.
+fn diagnostic_with_files<'a>(
+    error: &StackError,
+    sources: &[(&'a str, &'a str)],
+) -> (SimpleFiles<&'a str, &'a str>, Diagnostic<usize>) {
+    let mut files = SimpleFiles::new();
+    let ids: Vec<_> = sources
+        .iter()
+        .map(|(name, source)| (*name, files.add(*name, *source)))
+        .collect();
+    let diagnostic = to_diagnostic(error, |source_name| {
+        ids.iter()
+            .find(|(name, _)| *name == source_name)
+            .map(|(_, id)| *id)
+    });
+    (files, diagnostic)
+}
+
+/// Render an error with annotated source snippets as plain text. `sources`
+/// are pairs of source names and contents.
+pub fn render_diagnostic(
+    error: &StackError,
+    sources: &[(&str, &str)],
+) -> Result<String, codespan_reporting::files::Error> {
+    let (files, diagnostic) = diagnostic_with_files(error, sources);
+    term::emit_into_string(&term::Config::default(), &files, &diagnostic)
+}
+
+/// Emit an error with annotated source snippets to stderr, using colors if
+/// the terminal supports them. `sources` are pairs of source names and
+/// contents.
+pub fn emit_diagnostic(
+    error: &StackError,
+    sources: &[(&str, &str)],
+) -> Result<(), codespan_reporting::files::Error> {
+    let (files, diagnostic) = diagnostic_with_files(error, sources);
+    let stream = StandardStream::stderr(ColorChoice::Auto);
+    let mut writer = stream.lock();
+    term::emit_to_write_style(&mut writer, &term::Config::default(), &files, &diagnostic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_error_to_diagnostic() {
+        let error = StackError::from_msg("expected a number")
+            .with_err_code(ErrorCode::IoInvalidData)
+            .with_err_span("config.toml", 6..11)
+            .with_err_span("other.toml", 0..1)
+            .stack_err_msg("failed to load config");
+        let diagnostic = to_diagnostic(&error, |name| (name == "config.toml").then_some(0));
+        assert_eq!(diagnostic.message, "failed to load config");
+        assert_eq!(diagnostic.code.as_deref(), Some("IoInvalidData"));
+        assert_eq!(diagnostic.labels.len(), 1);
+        assert_eq!(diagnostic.labels[0].range, 6..11);
+        assert_eq!(diagnostic.notes, vec!["expected a number".to_string()]);
+    }
+
+    #[test]
+    fn test_render_diagnostic() {
+        let error = StackError::from_msg("expected a number").with_err_span("config.toml", 6..11);
+        let rendered = render_diagnostic(&error, &[("config.toml", "port: eight\n")]).unwrap();
+        assert!(rendered.contains("config.toml:1:7"));
+        assert!(rendered.contains("^^^^^ expected a number"));
+    }
+}
@@ -0,0 +1,105 @@
+//! Provides the [`ContextExt`] trait, with `anyhow`-style method names.
+//!
+//! This eases migrating code written with `anyhow::Context`: replacing the
+//! import with `stackerror::compat::ContextExt` keeps call sites compiling.
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+/// Extension trait providing `context` and `with_context` on [`Result`] and
+/// [`Option`].
+///
+/// On a [`Result`], these stack a new error with the given message, like
+/// [`ErrorStacks::stack_err_msg`]. On an [`Option`], they create a
+/// [`StackError`] with the given message if the option is `None`.
+pub trait ContextExt<T, E, C> {
+    /// Stack a new error with the given message.
+    #[track_caller]
+    fn context(self, message: impl std::fmt::Display + Send + Sync + 'static) -> Result<T, E>;
+
+    /// Stack a new error with a message which is only built on failure.
+    #[track_caller]
+    fn with_context<M>(self, message: impl FnOnce() -> M) -> Result<T, E>
+    where
+        M: std::fmt::Display + Send + Sync + 'static;
+}
+
+impl<T, E, C> ContextExt<T, E, C> for Result<T, E>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    E: ErrorStacks<C>,
+{
+    // These match instead of using `map_err`, as closures don't forward the
+    // caller location
+    fn context(self, message: impl std::fmt::Display + Send + Sync + 'static) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(e.stack_err_msg(message)),
+        }
+    }
+
+    fn with_context<M>(self, message: impl FnOnce() -> M) -> Result<T, E>
+    where
+        M: std::fmt::Display + Send + Sync + 'static,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(e.stack_err_msg(message())),
+        }
+    }
+}
+
+impl<T> ContextExt<T, StackError, ErrorCode> for Option<T> {
+    fn context(
+        self,
+        message: impl std::fmt::Display + Send + Sync + 'static,
+    ) -> Result<T, StackError> {
+        match self {
+            Some(value) => Ok(value),
+            None => Err(StackError::from_msg(message)),
+        }
+    }
+
+    fn with_context<M>(self, message: impl FnOnce() -> M) -> Result<T, StackError>
+    where
+        M: std::fmt::Display + Send + Sync + 'static,
+    {
+        match self {
+            Some(value) => Ok(value),
+            None => Err(StackError::from_msg(message())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_context_stacks() {
+        let result: Result<(), StackError> = Err(StackError::from_msg("Base error"));
+        let line = line!() + 1;
+        let error = result.context("Stacked error").unwrap_err();
+        assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+        let location = error.err_location().unwrap();
+        assert_eq!((location.file(), location.line()), ("src/compat.rs", line));
+    }
+
+    #[test]
+    fn test_with_context_is_lazy() {
+        let result: Result<u32, StackError> = Ok(1);
+        let value = result
+            .with_context(|| -> String { panic!("message built on success") })
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    fn test_option_context() {
+        let error = None::<u32>
+            .with_context(|| format!("missing {}", "value"))
+            .unwrap_err();
+        assert_eq!(format!("{:?}", error), "missing value");
+        assert_eq!(Some(1).context("missing value").unwrap(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+//! Provides the process-wide [`StackErrorConfig`], which controls how errors
+//! are captured and rendered at runtime.
+//!
+//! The configuration is set once at startup with [`set_config`], e.g. from an
+//! application's own configuration file. If it isn't set, it is read from the
+//! environment the first time it is used, so deployed binaries can adjust
+//! error behavior without recompiling:
+//!
+//! - `STACKERROR_BACKTRACE`: capture backtraces of panics (`1`) or never
+//!   capture them (`0`). Unset follows `RUST_BACKTRACE`.
+//! - `STACKERROR_LOCATION`: prefix [`fmt_loc!`](crate::fmt_loc) messages with
+//!   the file and line (default `1`).
+//! - `STACKERROR_VERBOSITY`: the [`Verbosity`] of `Debug` output.
+//! - `STACKERROR_REDACT`: apply the [redactor](crate::redact) to errors leaving
+//!   the process (default `1`).
+//! - `STACKERROR_MAX_DEPTH`: the maximum number of frames kept in an error.
+
+use std::sync::OnceLock;
+
+use crate::render::Verbosity;
+
+/// The runtime configuration of error capture and rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackErrorConfig {
+    /// Whether the panic hook captures backtraces. `None` follows the
+    /// `RUST_BACKTRACE` and `RUST_LIB_BACKTRACE` environment variables.
+    pub backtrace: Option<bool>,
+    /// Whether [`fmt_loc!`](crate::fmt_loc) prefixes messages with the file
+    /// and line.
+    pub location: bool,
+    /// The verbosity of `Debug` output.
+    pub verbosity: Verbosity,
+    /// Whether the global redactor is applied.
+    pub redact: bool,
+    /// The maximum number of frames kept when stacking. When it is reached,
+    /// the frame above the root is dropped, so that the root cause and the
+    /// latest context are kept. Values below 2 keep 2 frames.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for StackErrorConfig {
+    fn default() -> Self {
+        Self {
+            backtrace: None,
+            location: true,
+            verbosity: Verbosity::default(),
+            redact: true,
+            max_depth: None,
+        }
+    }
+}
+
+impl StackErrorConfig {
+    /// Read the configuration from the `STACKERROR_*` environment variables.
+    /// Unset or invalid values use the defaults.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let default = Self::default();
+        Self {
+            backtrace: lookup("STACKERROR_BACKTRACE").and_then(|v| parse_bool(&v)),
+            location: lookup("STACKERROR_LOCATION")
+                .and_then(|v| parse_bool(&v))
+                .unwrap_or(default.location),
+            verbosity: lookup("STACKERROR_VERBOSITY")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.verbosity),
+            redact: lookup("STACKERROR_REDACT")
+                .and_then(|v| parse_bool(&v))
+                .unwrap_or(default.redact),
+            max_depth: lookup("STACKERROR_MAX_DEPTH").and_then(|v| v.trim().parse().ok()),
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+static CONFIG: OnceLock<StackErrorConfig> = OnceLock::new();
+
+/// Set the process-wide configuration. It can only be set once, before it is
+/// first used; otherwise the given configuration is returned as the error.
+pub fn set_config(config: StackErrorConfig) -> Result<(), StackErrorConfig> {
+    CONFIG.set(config)
+}
+
+/// Get the process-wide configuration, reading it from the environment if it
+/// wasn't set with [`set_config`].
+pub fn config() -> &'static StackErrorConfig {
+    CONFIG.get_or_init(StackErrorConfig::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_lookup() {
+        let config = StackErrorConfig::from_lookup(|name| match name {
+            "STACKERROR_BACKTRACE" => Some("0".to_string()),
+            "STACKERROR_LOCATION" => Some("off".to_string()),
+            "STACKERROR_VERBOSITY" => Some("full".to_string()),
+            "STACKERROR_MAX_DEPTH" => Some(" 8 ".to_string()),
+            "STACKERROR_REDACT" => Some("invalid".to_string()),
+            _ => None,
+        });
+        assert_eq!(
+            config,
+            StackErrorConfig {
+                backtrace: Some(false),
+                location: false,
+                verbosity: Verbosity::Full,
+                redact: true,
+                max_depth: Some(8),
+            }
+        );
+        assert_eq!(
+            StackErrorConfig::from_lookup(|_| None),
+            StackErrorConfig::default()
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Provides ambient key-value context that is attached to new errors.
+//!
+//! Context pushed with [`push_err_context`] is recorded as fields on every
+//! [`StackError`](crate::error::StackError) created on the current thread
+//! while the returned guard is alive. With the `tokio` feature,
+//! [`with_error_context`] does the same for every error created inside a
+//! task-local scope.
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+thread_local! {
+    static CONTEXT: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_CONTEXT: Vec<(String, String)>;
+}
+
+/// Guard returned by [`push_err_context`]. The context is removed when the
+/// guard is dropped.
+#[must_use = "the context is removed when the guard is dropped"]
+pub struct ErrorContextGuard {
+    depth: usize,
+    // The guard must be dropped on the thread that created it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ErrorContextGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|context| context.borrow_mut().truncate(self.depth));
+    }
+}
+
+/// Push a key-value pair onto the current thread's ambient error context.
+///
+/// ```rust
+/// let _guard = push_err_context("job_id", job.id);
+/// // any StackError created here includes the `job_id` field
+/// ```
+pub fn push_err_context(
+    key: impl Into<String>,
+    value: impl std::fmt::Display,
+) -> ErrorContextGuard {
+    let depth = CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        let depth = context.len();
+        context.push((key.into(), value.to_string()));
+        depth
+    });
+    ErrorContextGuard {
+        depth,
+        _not_send: PhantomData,
+    }
+}
+
+/// Run a future with key-value pairs added to its task-local ambient error
+/// context. Scopes can be nested, in which case the inner scope extends the
+/// outer one.
+///
+/// ```rust
+/// let response = with_error_context([("request_id", request.id)], handle(request)).await;
+/// ```
+#[cfg(feature = "tokio")]
+pub fn with_error_context<K, V, F>(
+    context: impl IntoIterator<Item = (K, V)>,
+    future: F,
+) -> impl std::future::Future<Output = F::Output>
+where
+    K: Into<String>,
+    V: std::fmt::Display,
+    F: std::future::Future,
+{
+    let context: Vec<_> = context
+        .into_iter()
+        .map(|(key, value)| (key.into(), value.to_string()))
+        .collect();
+    async move {
+        // Read the outer scope when polled, as it might not be entered yet
+        let mut fields = TASK_CONTEXT
+            .try_with(|fields| fields.clone())
+            .unwrap_or_default();
+        fields.extend(context);
+        TASK_CONTEXT.scope(fields, future).await
+    }
+}
+
+/// Get the ambient context fields for the current task and thread. With the
+/// `tracing` feature, they are preceded by the fields recorded on the current
+/// span by [`SpanFieldsLayer`](crate::tracing::SpanFieldsLayer).
+pub(crate) fn context_fields() -> Vec<(String, String)> {
+    #[cfg(feature = "tracing")]
+    let mut fields = crate::tracing::current_span_fields();
+    #[cfg(not(feature = "tracing"))]
+    let mut fields = Vec::new();
+    #[cfg(feature = "tokio")]
+    fields.extend(
+        TASK_CONTEXT
+            .try_with(|fields| fields.clone())
+            .unwrap_or_default(),
+    );
+    CONTEXT.with(|context| fields.extend(context.borrow().iter().cloned()));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_context_adds_fields() {
+        let error = {
+            let _job = push_err_context("job_id", 7);
+            let _step = push_err_context("step", "load");
+            StackError::from_msg("Test error")
+        };
+        assert_eq!(
+            error.err_fields(),
+            &[
+                ("job_id".to_string(), "7".to_string()),
+                ("step".to_string(), "load".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_context_removed_on_drop() {
+        {
+            let _job = push_err_context("job_id", 7);
+        }
+        assert!(StackError::empty().err_fields().is_empty());
+    }
+
+    #[test]
+    fn test_context_not_added_to_stacked_frames() {
+        let error = {
+            let _job = push_err_context("job_id", 7);
+            StackError::from_msg("Base error").stack_err_msg("Stacked error")
+        };
+        assert!(error.err_fields().is_empty());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_task_context_adds_fields() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let error = runtime.block_on(with_error_context(
+            [("request_id", "abc")],
+            with_error_context([("tenant", "acme")], async {
+                StackError::from_msg("Test error")
+            }),
+        ));
+        assert_eq!(
+            error.err_fields(),
+            &[
+                ("request_id".to_string(), "abc".to_string()),
+                ("tenant".to_string(), "acme".to_string())
+            ]
+        );
+        assert!(StackError::empty().err_fields().is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+//! Provides crash report files for errors and panics which end the program.
+//!
+//! Crash reports are opt-in: once a directory is set with
+//! [`set_crash_report_dir`], errors returned from [`cli::run`] and panics
+//! caught by the hook installed with [`install_crash_hook`] are written to a
+//! report file in it, and its path is printed on standard error, so that
+//! users can attach it to a bug report:
+//!
+//! ```rust
+//! fn main() -> ExitCode {
+//!     stackerror::crash::set_crash_report_dir(std::env::temp_dir());
+//!     stackerror::crash::install_crash_hook();
+//!     stackerror::cli::run(|args: Args| { ... })
+//! }
+//! ```
+//!
+//! A report has the error's message and code, a snapshot of the program's
+//! environment, and its frames as rendered by
+//! [`StackError::render_verbose`], which include panic backtraces.
+//!
+//! [`cli::run`]: crate::cli::run
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use crate::error::{ErrorStacks, StackError};
+use crate::redact::redact;
+
+static CRASH_REPORT_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set the directory crash reports are written to, enabling them.
+pub fn set_crash_report_dir(dir: impl Into<PathBuf>) {
+    *CRASH_REPORT_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(dir.into());
+}
+
+/// Disable crash reports.
+pub fn clear_crash_report_dir() {
+    *CRASH_REPORT_DIR.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Get the directory crash reports are written to, if they are enabled.
+pub fn crash_report_dir() -> Option<PathBuf> {
+    CRASH_REPORT_DIR
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Write a crash report for the error if they are enabled, and print its
+/// path on standard error. Returns the path of the report.
+///
+/// This is called by [`cli::run`](crate::cli::run), and can be called by
+/// programs with their own `main` harness before exiting.
+pub fn report_crash(error: &StackError) -> Option<PathBuf> {
+    let dir = crash_report_dir()?;
+    match write_crash_report(&dir, error) {
+        Ok(path) => {
+            eprintln!("A crash report was written to {}", path.display());
+            Some(path)
+        }
+        Err(write_error) => {
+            eprintln!(
+                "Failed to write a crash report to {}: {write_error}",
+                dir.display()
+            );
+            None
+        }
+    }
+}
+
+/// Write a crash report for the error to a new file in the directory, which
+/// is created if needed. Returns the path of the report.
+pub fn write_crash_report(dir: &Path, error: &StackError) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let since_epoch = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let name = format!(
+        "crash-{}-{}-{}.txt",
+        since_epoch.as_secs(),
+        since_epoch.subsec_millis(),
+        std::process::id()
+    );
+    let path = dir.join(name);
+    let mut file = std::fs::File::create_new(&path)?;
+    file.write_all(crash_report(error).as_bytes())?;
+    Ok(path)
+}
+
+/// Render the crash report for the error. Messages and arguments are passed
+/// through the [redactor](crate::redact), as reports leave the machine.
+pub fn crash_report(error: &StackError) -> String {
+    let mut out = String::from("# Crash report\n\n");
+    out += &format!("error: {}\n", redact(&error.frame_msg().to_string()));
+    if let Some(code) = error.err_code() {
+        out += &format!("code: {code:?}\n");
+    }
+
+    out += "\n## Environment\n\n";
+    let args: Vec<_> = std::env::args_os()
+        .map(|arg| redact(&arg.to_string_lossy()))
+        .collect();
+    out += &format!("args: {args:?}\n");
+    out += &format!(
+        "os: {} ({})\n",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    out += &format!("pid: {}\n", std::process::id());
+    if let Ok(dir) = std::env::current_dir() {
+        out += &format!("cwd: {}\n", dir.display());
+    }
+    out += &format!("stackerror: {}\n", env!("CARGO_PKG_VERSION"));
+    // Only variables which affect error handling are recorded, as others can
+    // hold secrets
+    let mut vars: Vec<_> = std::env::vars()
+        .filter(|(key, _)| key.starts_with("RUST_") || key.starts_with("STACKERROR_"))
+        .collect();
+    vars.sort();
+    for (key, value) in vars {
+        out += &format!("{key}={value}\n");
+    }
+
+    out += "\n## Frames\n\n";
+    out += &redact(&error.render_verbose());
+    out
+}
+
+/// Install a panic hook which writes a crash report for panics, if crash
+/// reports are enabled. The report has the panic location as a field and, if
+/// enabled, the backtrace. The previously installed hook is still called.
+pub fn install_crash_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let error = crate::panic::from_panic_info(info);
+        previous(info);
+        report_crash(&error);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_write_crash_report() {
+        let dir = std::env::temp_dir().join(format!("stackerror-crash-{}", std::process::id()));
+        let line = line!();
+        let error = StackError::from_msg("Base error")
+            .with_err_field("path", "data.bin")
+            .stack_err_msg("Stacked error")
+            .with_err_code(ErrorCode::IoNotFound);
+        let path = write_crash_report(&dir, &error).unwrap();
+        let report = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(report.starts_with("# Crash report\n\nerror: Stacked error\ncode: IoNotFound\n"));
+        assert!(report.contains(&format!("\npid: {}\n", std::process::id())));
+        assert!(report.contains(&format!(
+            "\n## Frames\n\n0: Base error\n   location: src/crash.rs:{}:21\n   path: data.bin\n",
+            line + 1
+        )));
+        assert!(report.contains(&format!(
+            "\n1: Stacked error\n   code: IoNotFound\n   location: src/crash.rs:{}:14\n",
+            line + 3
+        )));
+    }
+}
@@ -0,0 +1,168 @@
+//! Provides the [`Diagnostics`] collector for tools which should not stop at
+//! the first problem.
+
+use crate::error::{ErrorStacks, StackError};
+
+/// The severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticLevel {
+    /// A problem which doesn't fail the operation.
+    Warning,
+    /// A problem which fails the operation, once all diagnostics are
+    /// collected.
+    Error,
+}
+
+impl std::fmt::Display for DiagnosticLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticLevel::Warning => write!(f, "warning"),
+            DiagnosticLevel::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Accumulates errors and warnings during an operation, e.g. linting or
+/// migrating a configuration.
+///
+/// ```rust
+/// let mut diagnostics = Diagnostics::new();
+/// for entry in entries {
+///     if let Some(value) = diagnostics.check(parse(entry)) {
+///         values.push(value);
+///     }
+/// }
+/// let diagnostics = diagnostics.into_result("invalid configuration")?;
+/// eprint!("{}", diagnostics.report());
+/// ```
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: Vec<(DiagnosticLevel, StackError)>,
+}
+
+impl Diagnostics {
+    /// Creates an empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a warning.
+    pub fn warn(&mut self, warning: impl Into<StackError>) {
+        self.entries
+            .push((DiagnosticLevel::Warning, warning.into()));
+    }
+
+    /// Record an error.
+    pub fn error(&mut self, error: impl Into<StackError>) {
+        self.entries.push((DiagnosticLevel::Error, error.into()));
+    }
+
+    /// Record the error of a result, if any, and return its value otherwise.
+    pub fn check<T, E: Into<StackError>>(&mut self, result: Result<T, E>) -> Option<T> {
+        result.map_err(|error| self.error(error)).ok()
+    }
+
+    /// Iterate over the diagnostics in the order they were recorded.
+    pub fn iter(&self) -> impl Iterator<Item = (DiagnosticLevel, &StackError)> {
+        self.entries.iter().map(|(level, error)| (*level, error))
+    }
+
+    /// Get the number of diagnostics with the given level.
+    pub fn count(&self, level: DiagnosticLevel) -> usize {
+        self.iter().filter(|(l, _)| *l == level).count()
+    }
+
+    /// Check if any errors were recorded.
+    pub fn has_errors(&self) -> bool {
+        self.count(DiagnosticLevel::Error) > 0
+    }
+
+    /// Check if no diagnostics were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Render the diagnostics, one per line prefixed with its level. The
+    /// frames of each diagnostic are on indented lines, from the root to the
+    /// top.
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        for (level, error) in self.iter() {
+            out += &format!("{level}: {error}\n");
+            let frames: Vec<_> = std::iter::successors(Some(error), |e| {
+                std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+            })
+            .skip(1)
+            .collect();
+            for frame in frames.iter().rev() {
+                out += &format!("  {}\n", frame.frame_msg());
+            }
+        }
+        out
+    }
+
+    /// Convert into an error with the given message if any errors were
+    /// recorded, or return the collector with its warnings otherwise.
+    ///
+    /// The error has `errors` and `warnings` count fields, and the collector
+    /// is attached to it, so that it can be retrieved with
+    /// [`StackError::get_attachment`].
+    pub fn into_result(
+        self,
+        message: impl std::fmt::Display + Send + Sync + 'static,
+    ) -> Result<Self, StackError> {
+        if !self.has_errors() {
+            return Ok(self);
+        }
+        Err(StackError::from_msg(message)
+            .with_err_field("errors", self.count(DiagnosticLevel::Error))
+            .with_err_field("warnings", self.count(DiagnosticLevel::Warning))
+            .attach(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(entry: &str) -> Result<u32, StackError> {
+        entry
+            .parse()
+            .map_err(|_| StackError::from_msg(format!("invalid number {entry:?}")))
+    }
+
+    #[test]
+    fn test_diagnostics_collects() {
+        let mut diagnostics = Diagnostics::new();
+        let values: Vec<_> = ["1", "x", "3"]
+            .into_iter()
+            .filter_map(|entry| diagnostics.check(parse(entry)))
+            .collect();
+        diagnostics.warn(StackError::from_msg("deprecated key").stack_err_msg("in section [a]"));
+        assert_eq!(values, [1, 3]);
+        assert_eq!(
+            diagnostics.report(),
+            "error: invalid number \"x\"\nwarning: in section [a]\n  deprecated key\n"
+        );
+        let error = diagnostics
+            .into_result("invalid configuration")
+            .unwrap_err();
+        assert_eq!(
+            error.err_fields(),
+            &[
+                ("errors".to_string(), "1".to_string()),
+                ("warnings".to_string(), "1".to_string())
+            ]
+        );
+        let diagnostics = error.get_attachment::<Diagnostics>().unwrap();
+        assert_eq!(diagnostics.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_diagnostics_warnings_only() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.warn(StackError::from_msg("deprecated key"));
+        let diagnostics = diagnostics.into_result("invalid configuration").unwrap();
+        assert_eq!(diagnostics.count(DiagnosticLevel::Warning), 1);
+    }
+}
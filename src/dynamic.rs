@@ -0,0 +1,444 @@
+//! Provides the object-safe [`DynErrorStacks`] trait, so that different
+//! stacking error types can be stored and stacked behind trait objects.
+//!
+//! [`ErrorStacks`] takes `impl Trait` arguments and returns `Self`, so it
+//! can't be used as `dyn ErrorStacks`. [`DynErrorStacks`] is implemented for
+//! every error implementing [`ErrorStacks`], and [`BoxStackError`] implements
+//! [`ErrorStacks`] by forwarding to it, so boxed errors stack as usual:
+//!
+//! ```rust
+//! let errors: Vec<BoxStackError> = vec![
+//!     Box::new(StackError::from_msg("Base error")),
+//!     Box::new(LibError::from_msg("Library error")),
+//! ];
+//! let errors: Vec<_> = errors
+//!     .into_iter()
+//!     .map(|e| e.stack_err_msg("Stacked error"))
+//!     .collect();
+//! ```
+
+use std::fmt::Display;
+
+use crate::codes::ErrorCode;
+use crate::error::ErrorStacks;
+use crate::hint::RecoveryHint;
+use crate::origin::ErrorOrigin;
+use crate::severity::Severity;
+use crate::span::{ErrorPosition, ErrorSpan};
+
+/// A boxed stacking error of any type.
+pub type BoxStackError<C = ErrorCode> = Box<dyn DynErrorStacks<C>>;
+
+type BoxDisplay = Box<dyn Display + Send + Sync + 'static>;
+
+/// Object-safe version of [`ErrorStacks`]. Methods are prefixed with `dyn_`
+/// so that they don't shadow those of [`ErrorStacks`], and those which
+/// modify the error take and return a box.
+pub trait DynErrorStacks<C = ErrorCode>: std::error::Error + Send + Sync + 'static
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+{
+    /// See [`ErrorStacks::err_code`].
+    fn dyn_err_code(&self) -> Option<&C>;
+    /// See [`ErrorStacks::with_err_code`].
+    fn dyn_with_err_code(self: Box<Self>, code: C) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_code`].
+    fn dyn_with_no_err_code(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_uri`].
+    fn dyn_err_uri(&self) -> Option<&str>;
+    /// See [`ErrorStacks::with_err_uri`].
+    fn dyn_with_err_uri(self: Box<Self>, uri: String) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_uri`].
+    fn dyn_with_no_err_uri(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_fields`].
+    fn dyn_err_fields(&self) -> &[(String, String)];
+    /// See [`ErrorStacks::with_err_field`].
+    fn dyn_with_err_field(self: Box<Self>, key: String, value: &dyn Display) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_fields`].
+    fn dyn_with_no_err_fields(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_spans`].
+    fn dyn_err_spans(&self) -> &[ErrorSpan];
+    /// See [`ErrorStacks::with_err_span`].
+    fn dyn_with_err_span(
+        self: Box<Self>,
+        source_name: String,
+        range: std::ops::Range<usize>,
+    ) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_spans`].
+    fn dyn_with_no_err_spans(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_position`].
+    fn dyn_err_position(&self) -> Option<&ErrorPosition>;
+    /// See [`ErrorStacks::with_err_position`].
+    fn dyn_with_err_position(
+        self: Box<Self>,
+        offset: usize,
+        line: usize,
+        column: usize,
+    ) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_position`].
+    fn dyn_with_no_err_position(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_hint`].
+    fn dyn_err_hint(&self) -> Option<&RecoveryHint>;
+    /// See [`ErrorStacks::with_err_hint`].
+    fn dyn_with_err_hint(self: Box<Self>, hint: RecoveryHint) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_hint`].
+    fn dyn_with_no_err_hint(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_origin`].
+    fn dyn_err_origin(&self) -> Option<&ErrorOrigin>;
+    /// See [`ErrorStacks::with_err_origin`].
+    fn dyn_with_err_origin(self: Box<Self>, origin: ErrorOrigin) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_origin`].
+    fn dyn_with_no_err_origin(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_severity`].
+    fn dyn_err_severity(&self) -> Option<&Severity>;
+    /// See [`ErrorStacks::with_err_severity`].
+    fn dyn_with_err_severity(self: Box<Self>, severity: Severity) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_severity`].
+    fn dyn_with_no_err_severity(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_elapsed`].
+    fn dyn_err_elapsed(&self) -> Option<std::time::Duration>;
+    /// See [`ErrorStacks::with_err_elapsed`].
+    fn dyn_with_err_elapsed(self: Box<Self>, elapsed: std::time::Duration) -> BoxStackError<C>;
+    /// See [`ErrorStacks::err_deadline`].
+    fn dyn_err_deadline(&self) -> Option<std::time::Instant>;
+    /// See [`ErrorStacks::with_err_deadline`].
+    fn dyn_with_err_deadline(self: Box<Self>, deadline: std::time::Instant) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_err_msg`].
+    #[track_caller]
+    fn dyn_with_err_msg(self: Box<Self>, error: BoxDisplay) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_no_err_msg`].
+    fn dyn_with_no_err_msg(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::stack_err`].
+    #[track_caller]
+    fn dyn_stack_err(self: Box<Self>) -> BoxStackError<C>;
+    /// See [`ErrorStacks::stack_err_msg`].
+    #[track_caller]
+    fn dyn_stack_err_msg(self: Box<Self>, error: BoxDisplay) -> BoxStackError<C>;
+    /// See [`ErrorStacks::with_err_location`].
+    fn dyn_with_err_location(
+        self: Box<Self>,
+        location: &'static std::panic::Location<'static>,
+    ) -> BoxStackError<C>;
+}
+
+impl<T, C> DynErrorStacks<C> for T
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    T: ErrorStacks<C> + std::error::Error + Send + Sync + 'static,
+{
+    fn dyn_err_code(&self) -> Option<&C> {
+        self.err_code()
+    }
+
+    fn dyn_with_err_code(self: Box<Self>, code: C) -> BoxStackError<C> {
+        Box::new(self.with_err_code(code))
+    }
+
+    fn dyn_with_no_err_code(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_code())
+    }
+
+    fn dyn_err_uri(&self) -> Option<&str> {
+        self.err_uri()
+    }
+
+    fn dyn_with_err_uri(self: Box<Self>, uri: String) -> BoxStackError<C> {
+        Box::new(self.with_err_uri(uri))
+    }
+
+    fn dyn_with_no_err_uri(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_uri())
+    }
+
+    fn dyn_err_fields(&self) -> &[(String, String)] {
+        self.err_fields()
+    }
+
+    fn dyn_with_err_field(self: Box<Self>, key: String, value: &dyn Display) -> BoxStackError<C> {
+        Box::new(self.with_err_field(key, value))
+    }
+
+    fn dyn_with_no_err_fields(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_fields())
+    }
+
+    fn dyn_err_spans(&self) -> &[ErrorSpan] {
+        self.err_spans()
+    }
+
+    fn dyn_with_err_span(
+        self: Box<Self>,
+        source_name: String,
+        range: std::ops::Range<usize>,
+    ) -> BoxStackError<C> {
+        Box::new(self.with_err_span(source_name, range))
+    }
+
+    fn dyn_with_no_err_spans(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_spans())
+    }
+
+    fn dyn_err_position(&self) -> Option<&ErrorPosition> {
+        self.err_position()
+    }
+
+    fn dyn_with_err_position(
+        self: Box<Self>,
+        offset: usize,
+        line: usize,
+        column: usize,
+    ) -> BoxStackError<C> {
+        Box::new(self.with_err_position(offset, line, column))
+    }
+
+    fn dyn_with_no_err_position(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_position())
+    }
+
+    fn dyn_err_hint(&self) -> Option<&RecoveryHint> {
+        self.err_hint()
+    }
+
+    fn dyn_with_err_hint(self: Box<Self>, hint: RecoveryHint) -> BoxStackError<C> {
+        Box::new(self.with_err_hint(hint))
+    }
+
+    fn dyn_with_no_err_hint(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_hint())
+    }
+
+    fn dyn_err_origin(&self) -> Option<&ErrorOrigin> {
+        self.err_origin()
+    }
+
+    fn dyn_with_err_origin(self: Box<Self>, origin: ErrorOrigin) -> BoxStackError<C> {
+        Box::new(self.with_err_origin(origin))
+    }
+
+    fn dyn_with_no_err_origin(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_origin())
+    }
+
+    fn dyn_err_severity(&self) -> Option<&Severity> {
+        self.err_severity()
+    }
+
+    fn dyn_with_err_severity(self: Box<Self>, severity: Severity) -> BoxStackError<C> {
+        Box::new(self.with_err_severity(severity))
+    }
+
+    fn dyn_with_no_err_severity(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_severity())
+    }
+
+    fn dyn_err_elapsed(&self) -> Option<std::time::Duration> {
+        self.err_elapsed()
+    }
+
+    fn dyn_with_err_elapsed(self: Box<Self>, elapsed: std::time::Duration) -> BoxStackError<C> {
+        Box::new(self.with_err_elapsed(elapsed))
+    }
+
+    fn dyn_err_deadline(&self) -> Option<std::time::Instant> {
+        self.err_deadline()
+    }
+
+    fn dyn_with_err_deadline(self: Box<Self>, deadline: std::time::Instant) -> BoxStackError<C> {
+        Box::new(self.with_err_deadline(deadline))
+    }
+
+    fn dyn_with_err_msg(self: Box<Self>, error: BoxDisplay) -> BoxStackError<C> {
+        Box::new(self.with_err_msg(error))
+    }
+
+    fn dyn_with_no_err_msg(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.with_no_err_msg())
+    }
+
+    fn dyn_stack_err(self: Box<Self>) -> BoxStackError<C> {
+        Box::new(self.stack_err())
+    }
+
+    fn dyn_stack_err_msg(self: Box<Self>, error: BoxDisplay) -> BoxStackError<C> {
+        Box::new(self.stack_err_msg(error))
+    }
+
+    fn dyn_with_err_location(
+        self: Box<Self>,
+        location: &'static std::panic::Location<'static>,
+    ) -> BoxStackError<C> {
+        Box::new(self.with_err_location(location))
+    }
+}
+
+/// Implementation for boxed errors forwards to [`DynErrorStacks`].
+impl<C> ErrorStacks<C> for BoxStackError<C>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+{
+    fn err_code(&self) -> Option<&C> {
+        self.as_ref().dyn_err_code()
+    }
+
+    fn with_err_code(self, code: C) -> Self {
+        self.dyn_with_err_code(code)
+    }
+
+    fn with_no_err_code(self) -> Self {
+        self.dyn_with_no_err_code()
+    }
+
+    fn err_uri(&self) -> Option<&str> {
+        self.as_ref().dyn_err_uri()
+    }
+
+    fn with_err_uri(self, uri: String) -> Self {
+        self.dyn_with_err_uri(uri)
+    }
+
+    fn with_no_err_uri(self) -> Self {
+        self.dyn_with_no_err_uri()
+    }
+
+    fn err_fields(&self) -> &[(String, String)] {
+        self.as_ref().dyn_err_fields()
+    }
+
+    fn with_err_field(self, key: impl Into<String>, value: impl Display) -> Self {
+        self.dyn_with_err_field(key.into(), &value)
+    }
+
+    fn with_no_err_fields(self) -> Self {
+        self.dyn_with_no_err_fields()
+    }
+
+    fn err_spans(&self) -> &[ErrorSpan] {
+        self.as_ref().dyn_err_spans()
+    }
+
+    fn with_err_span(self, source_name: impl Into<String>, range: std::ops::Range<usize>) -> Self {
+        self.dyn_with_err_span(source_name.into(), range)
+    }
+
+    fn with_no_err_spans(self) -> Self {
+        self.dyn_with_no_err_spans()
+    }
+
+    fn err_position(&self) -> Option<&ErrorPosition> {
+        self.as_ref().dyn_err_position()
+    }
+
+    fn with_err_position(self, offset: usize, line: usize, column: usize) -> Self {
+        self.dyn_with_err_position(offset, line, column)
+    }
+
+    fn with_no_err_position(self) -> Self {
+        self.dyn_with_no_err_position()
+    }
+
+    fn err_hint(&self) -> Option<&RecoveryHint> {
+        self.as_ref().dyn_err_hint()
+    }
+
+    fn with_err_hint(self, hint: RecoveryHint) -> Self {
+        self.dyn_with_err_hint(hint)
+    }
+
+    fn with_no_err_hint(self) -> Self {
+        self.dyn_with_no_err_hint()
+    }
+
+    fn err_origin(&self) -> Option<&ErrorOrigin> {
+        self.as_ref().dyn_err_origin()
+    }
+
+    fn with_err_origin(self, origin: ErrorOrigin) -> Self {
+        self.dyn_with_err_origin(origin)
+    }
+
+    fn with_no_err_origin(self) -> Self {
+        self.dyn_with_no_err_origin()
+    }
+
+    fn err_severity(&self) -> Option<&Severity> {
+        self.as_ref().dyn_err_severity()
+    }
+
+    fn with_err_severity(self, severity: Severity) -> Self {
+        self.dyn_with_err_severity(severity)
+    }
+
+    fn with_no_err_severity(self) -> Self {
+        self.dyn_with_no_err_severity()
+    }
+
+    fn err_elapsed(&self) -> Option<std::time::Duration> {
+        self.as_ref().dyn_err_elapsed()
+    }
+
+    fn with_err_elapsed(self, elapsed: std::time::Duration) -> Self {
+        self.dyn_with_err_elapsed(elapsed)
+    }
+
+    fn err_deadline(&self) -> Option<std::time::Instant> {
+        self.as_ref().dyn_err_deadline()
+    }
+
+    fn with_err_deadline(self, deadline: std::time::Instant) -> Self {
+        self.dyn_with_err_deadline(deadline)
+    }
+
+    fn with_err_msg(self, error: impl Display + Send + Sync + 'static) -> Self {
+        self.dyn_with_err_msg(Box::new(error))
+    }
+
+    fn with_no_err_msg(self) -> Self {
+        self.dyn_with_no_err_msg()
+    }
+
+    fn stack_err(self) -> Self {
+        self.dyn_stack_err()
+    }
+
+    fn stack_err_msg(self, error: impl Display + Send + Sync + 'static) -> Self {
+        self.dyn_stack_err_msg(Box::new(error))
+    }
+
+    fn with_err_location(self, location: &'static std::panic::Location<'static>) -> Self {
+        self.dyn_with_err_location(location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::derive_stack_error;
+    use crate::prelude::*;
+
+    #[derive_stack_error]
+    struct LibError(StackError);
+
+    #[test]
+    fn test_boxed_errors_stack() {
+        let errors: Vec<BoxStackError> = vec![
+            Box::new(StackError::from_msg("Base error")),
+            Box::new(LibError::from_msg("Library error")),
+        ];
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|e| {
+                e.with_err_code(ErrorCode::IoNotFound)
+                    .stack_err_msg("Stacked error")
+                    .with_err_field("key", "value")
+            })
+            .collect();
+        for (error, base) in errors.iter().zip(["Base error", "Library error"]) {
+            assert_eq!(format!("{:?}", error), format!("{base}\nStacked error"));
+            assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+            assert_eq!(
+                error.err_fields(),
+                &[("key".to_string(), "value".to_string())]
+            );
+        }
+        assert!(errors[1].as_ref().source().is_some());
+    }
+}
@@ -0,0 +1,127 @@
+//! Provides [`StackError::emit`], which logs an error at a level chosen from
+//! its severity or code, to report errors the same way at every catch site.
+//!
+//! With the `tracing` feature, errors are emitted as `tracing` events with
+//! the error recorded as the `error` field, so that
+//! [`StackErrorLayer`](crate::tracing::StackErrorLayer) can render them.
+//! Otherwise, with the `log` feature, they are emitted as `log` records with
+//! the error's [key-value pairs](StackError::kv_pairs).
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::severity::Severity;
+
+/// Get the level at which errors with the code, and without a severity, are
+/// emitted. Errors caused by the client, with HTTP 4xx codes such as
+/// [`ErrorCode::HttpTooManyRequests`], are warnings. Other errors, including
+/// HTTP 5xx codes and errors without a code, are errors.
+pub fn emit_level(code: Option<ErrorCode>) -> Severity {
+    match code.and_then(ErrorCode::to_http_value) {
+        Some(400..=499) => Severity::Warn,
+        _ => Severity::Error,
+    }
+}
+
+impl StackError {
+    /// Get the level at which the error is emitted: its severity if set, or
+    /// else the level for its code from [`emit_level`].
+    pub fn emit_level(&self) -> Severity {
+        match self.err_severity() {
+            Some(severity) => *severity,
+            None => emit_level(self.err_code().copied()),
+        }
+    }
+
+    /// Log the error at its [level](StackError::emit_level).
+    #[cfg(feature = "tracing")]
+    pub fn emit(&self) {
+        let error = self as &(dyn std::error::Error + 'static);
+        match self.emit_level() {
+            Severity::Debug => ::tracing::debug!(error, "{self}"),
+            Severity::Info => ::tracing::info!(error, "{self}"),
+            Severity::Warn => ::tracing::warn!(error, "{self}"),
+            Severity::Error | Severity::Fatal => ::tracing::error!(error, "{self}"),
+        }
+    }
+
+    /// Log the error at its [level](StackError::emit_level), with the
+    /// caller's location.
+    #[cfg(all(feature = "log", not(feature = "tracing")))]
+    #[track_caller]
+    pub fn emit(&self) {
+        let level = match self.emit_level() {
+            Severity::Debug => log::Level::Debug,
+            Severity::Info => log::Level::Info,
+            Severity::Warn => log::Level::Warn,
+            Severity::Error | Severity::Fatal => log::Level::Error,
+        };
+        if level > log::max_level() {
+            return;
+        }
+        let location = std::panic::Location::caller();
+        let pairs = self.kv_pairs();
+        log::logger().log(
+            &log::Record::builder()
+                .args(format_args!("{self}"))
+                .level(level)
+                .target(module_path!())
+                .file(Some(location.file()))
+                .line(Some(location.line()))
+                .key_values(&pairs)
+                .build(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emit_level() {
+        assert_eq!(emit_level(None), Severity::Error);
+        assert_eq!(emit_level(Some(ErrorCode::IoNotFound)), Severity::Error);
+        assert_eq!(
+            emit_level(Some(ErrorCode::HttpServiceUnavailable)),
+            Severity::Error
+        );
+        assert_eq!(
+            emit_level(Some(ErrorCode::HttpTooManyRequests)),
+            Severity::Warn
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_emit_tracing_event() {
+        use std::sync::{Arc, Mutex};
+
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        struct Levels(Arc<Mutex<Vec<::tracing::Level>>>);
+
+        impl<S: ::tracing::Subscriber> Layer<S> for Levels {
+            fn on_event(&self, event: &::tracing::Event<'_>, _ctx: Context<'_, S>) {
+                self.0.lock().unwrap().push(*event.metadata().level());
+            }
+        }
+
+        let levels = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(Levels(levels.clone()));
+        ::tracing::subscriber::with_default(subscriber, || {
+            StackError::internal("Server error").emit();
+            StackError::too_many_requests("Rate limited").emit();
+            StackError::too_many_requests("Rate limited")
+                .with_err_severity(Severity::Info)
+                .emit();
+        });
+        assert_eq!(
+            *levels.lock().unwrap(),
+            [
+                ::tracing::Level::ERROR,
+                ::tracing::Level::WARN,
+                ::tracing::Level::INFO
+            ]
+        );
+    }
+}
@@ -2,10 +2,19 @@
 //! trait.
 
 use crate::codes::ErrorCode;
+use crate::context::context_fields;
+use crate::hint::RecoveryHint;
+use crate::origin::ErrorOrigin;
+use crate::severity::Severity;
+use crate::span::{ErrorPosition, ErrorSpan};
 
 /// Trait for stacking errors: errors that stack and provide an optional error
 /// code and resource URI for runtime error handling.
-pub trait ErrorStacks<C>
+///
+/// The methods beyond codes, URIs, messages and stacking have default
+/// implementations which ignore the attribute, so that implementations
+/// written for earlier versions keep compiling.
+pub trait ErrorStacks<C>: Sized
 where
     C: Send + Sync + 'static + Eq + PartialEq + Clone,
 {
@@ -21,14 +30,121 @@ where
     fn with_err_uri(self, uri: String) -> Self;
     /// Remove the error URI.
     fn with_no_err_uri(self) -> Self;
-    /// Set the error message.
+    /// Get the key-value fields attached to the error.
+    fn err_fields(&self) -> &[(String, String)] {
+        &[]
+    }
+    /// Attach a key-value field to the error.
+    fn with_err_field(self, key: impl Into<String>, value: impl std::fmt::Display) -> Self {
+        let _ = (key.into(), value);
+        self
+    }
+    /// Remove all key-value fields.
+    fn with_no_err_fields(self) -> Self {
+        self
+    }
+    /// Get the source spans attached to the error.
+    fn err_spans(&self) -> &[ErrorSpan] {
+        &[]
+    }
+    /// Attach a source span to the error. Multiple spans can be attached.
+    fn with_err_span(self, source_name: impl Into<String>, range: std::ops::Range<usize>) -> Self {
+        let _ = (source_name.into(), range);
+        self
+    }
+    /// Remove all source spans.
+    fn with_no_err_spans(self) -> Self {
+        self
+    }
+    /// Get the input position attached to the error if one is set.
+    fn err_position(&self) -> Option<&ErrorPosition> {
+        None
+    }
+    /// Set the input position of the error, as a byte offset and 1-based line
+    /// and column numbers.
+    fn with_err_position(self, offset: usize, line: usize, column: usize) -> Self {
+        let _ = (offset, line, column);
+        self
+    }
+    /// Remove the input position.
+    fn with_no_err_position(self) -> Self {
+        self
+    }
+    /// Get the recovery hint if one is set.
+    fn err_hint(&self) -> Option<&RecoveryHint> {
+        None
+    }
+    /// Set the recovery hint.
+    fn with_err_hint(self, hint: RecoveryHint) -> Self {
+        let _ = hint;
+        self
+    }
+    /// Remove the recovery hint.
+    fn with_no_err_hint(self) -> Self {
+        self
+    }
+    /// Get the origin of the error if one is set.
+    fn err_origin(&self) -> Option<&ErrorOrigin> {
+        None
+    }
+    /// Set the origin of the error.
+    fn with_err_origin(self, origin: ErrorOrigin) -> Self {
+        let _ = origin;
+        self
+    }
+    /// Remove the origin.
+    fn with_no_err_origin(self) -> Self {
+        self
+    }
+    /// Get the severity of the error if one is set.
+    fn err_severity(&self) -> Option<&Severity> {
+        None
+    }
+    /// Set the severity of the error.
+    fn with_err_severity(self, severity: Severity) -> Self {
+        let _ = severity;
+        self
+    }
+    /// Remove the severity.
+    fn with_no_err_severity(self) -> Self {
+        self
+    }
+    /// Get the time spent on the operation before it failed, if set.
+    fn err_elapsed(&self) -> Option<std::time::Duration> {
+        None
+    }
+    /// Set the time spent on the operation before it failed.
+    fn with_err_elapsed(self, elapsed: std::time::Duration) -> Self {
+        let _ = elapsed;
+        self
+    }
+    /// Get the deadline of the operation, if set.
+    fn err_deadline(&self) -> Option<std::time::Instant> {
+        None
+    }
+    /// Set the deadline of the operation.
+    fn with_err_deadline(self, deadline: std::time::Instant) -> Self {
+        let _ = deadline;
+        self
+    }
+    /// Set the error message, and the location of the caller.
+    #[track_caller]
     fn with_err_msg(self, error: impl std::fmt::Display + Send + Sync + 'static) -> Self;
     /// Remove the error message.
     fn with_no_err_msg(self) -> Self;
-    /// Stack a new error on the current one.
+    /// Stack a new error on the current one, at the location of the caller.
+    #[track_caller]
     fn stack_err(self) -> Self;
-    /// Stack a new error on the current one with a given message.
+    /// Stack a new error on the current one with a given message, at the
+    /// location of the caller.
+    #[track_caller]
     fn stack_err_msg(self, error: impl std::fmt::Display + Send + Sync + 'static) -> Self;
+    /// Set the location of the top frame, e.g. to the caller of an adapter
+    /// which stacks the frame later.
+    fn with_err_location(self, location: &'static std::panic::Location<'static>) -> Self {
+        let _ = location;
+        self
+    }
 }
 
 /// Implementation for [`Result`] allows adding error codes on results.
@@ -61,8 +177,101 @@ where
         self.map_err(|e| e.with_no_err_uri())
     }
 
+    fn err_fields(&self) -> &[(String, String)] {
+        self.as_ref().err().map_or(&[], |e| e.err_fields())
+    }
+
+    fn with_err_field(self, key: impl Into<String>, value: impl std::fmt::Display) -> Self {
+        self.map_err(|e| e.with_err_field(key, value))
+    }
+
+    fn with_no_err_fields(self) -> Self {
+        self.map_err(|e| e.with_no_err_fields())
+    }
+
+    fn err_spans(&self) -> &[ErrorSpan] {
+        self.as_ref().err().map_or(&[], |e| e.err_spans())
+    }
+
+    fn with_err_span(self, source_name: impl Into<String>, range: std::ops::Range<usize>) -> Self {
+        self.map_err(|e| e.with_err_span(source_name, range))
+    }
+
+    fn with_no_err_spans(self) -> Self {
+        self.map_err(|e| e.with_no_err_spans())
+    }
+
+    fn err_position(&self) -> Option<&ErrorPosition> {
+        self.as_ref().err().and_then(|e| e.err_position())
+    }
+
+    fn with_err_position(self, offset: usize, line: usize, column: usize) -> Self {
+        self.map_err(|e| e.with_err_position(offset, line, column))
+    }
+
+    fn with_no_err_position(self) -> Self {
+        self.map_err(|e| e.with_no_err_position())
+    }
+
+    fn err_hint(&self) -> Option<&RecoveryHint> {
+        self.as_ref().err().and_then(|e| e.err_hint())
+    }
+
+    fn with_err_hint(self, hint: RecoveryHint) -> Self {
+        self.map_err(|e| e.with_err_hint(hint))
+    }
+
+    fn with_no_err_hint(self) -> Self {
+        self.map_err(|e| e.with_no_err_hint())
+    }
+
+    fn err_origin(&self) -> Option<&ErrorOrigin> {
+        self.as_ref().err().and_then(|e| e.err_origin())
+    }
+
+    fn with_err_origin(self, origin: ErrorOrigin) -> Self {
+        self.map_err(|e| e.with_err_origin(origin))
+    }
+
+    fn with_no_err_origin(self) -> Self {
+        self.map_err(|e| e.with_no_err_origin())
+    }
+
+    fn err_severity(&self) -> Option<&Severity> {
+        self.as_ref().err().and_then(|e| e.err_severity())
+    }
+
+    fn with_err_severity(self, severity: Severity) -> Self {
+        self.map_err(|e| e.with_err_severity(severity))
+    }
+
+    fn with_no_err_severity(self) -> Self {
+        self.map_err(|e| e.with_no_err_severity())
+    }
+
+    fn err_elapsed(&self) -> Option<std::time::Duration> {
+        self.as_ref().err().and_then(|e| e.err_elapsed())
+    }
+
+    fn with_err_elapsed(self, elapsed: std::time::Duration) -> Self {
+        self.map_err(|e| e.with_err_elapsed(elapsed))
+    }
+
+    fn err_deadline(&self) -> Option<std::time::Instant> {
+        self.as_ref().err().and_then(|e| e.err_deadline())
+    }
+
+    fn with_err_deadline(self, deadline: std::time::Instant) -> Self {
+        self.map_err(|e| e.with_err_deadline(deadline))
+    }
+
+    // These match instead of using `map_err`, as closures don't forward the
+    // caller location
     fn with_err_msg(self, error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
-        self.map_err(|e| e.with_err_msg(error))
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(e.with_err_msg(error)),
+        }
     }
 
     fn with_no_err_msg(self) -> Self {
@@ -70,45 +279,692 @@ where
     }
 
     fn stack_err(self) -> Self {
-        self.map_err(|e| e.stack_err())
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(e.stack_err()),
+        }
     }
 
     fn stack_err_msg(self, error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
-        self.map_err(|e| e.stack_err_msg(error))
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(e.stack_err_msg(error)),
+        }
+    }
+
+    fn with_err_location(self, location: &'static std::panic::Location<'static>) -> Self {
+        self.map_err(|e| e.with_err_location(location))
     }
 }
 
-/// A simple error type that implements the [`ErrorStacks`] trait.
+/// The message of a frame. Wrapped errors are kept as errors so that they can
+/// be downcast.
+enum Message {
+    Display(Box<dyn std::fmt::Display + Send + Sync + 'static>),
+    Error(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Display(message) => message.fmt(f),
+            Message::Error(error) => error.fmt(f),
+        }
+    }
+}
+
+/// Attachments of a frame which are rarely set. They are boxed so that the
+/// error stays small in the common case.
 #[derive(Default)]
-pub struct StackError {
-    message: Option<Box<dyn std::fmt::Display + Send + Sync + 'static>>,
-    source: Option<Box<StackError>>,
-    code: Option<ErrorCode>,
+struct Attachments {
+    spans: Vec<ErrorSpan>,
+    position: Option<ErrorPosition>,
+    hint: Option<RecoveryHint>,
+    origin: Option<ErrorOrigin>,
+    severity: Option<Severity>,
+    elapsed: Option<std::time::Duration>,
+    deadline: Option<std::time::Instant>,
+    trace_id: Option<String>,
+    span_id: Option<u64>,
+    #[cfg(feature = "span-trace")]
+    span_trace: Option<tracing_error::SpanTrace>,
+    backtrace: Option<std::backtrace::Backtrace>,
+    values: Vec<Box<dyn std::any::Any + Send + Sync>>,
+}
+
+/// Get the current time, to timestamp frames.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now() -> Option<std::time::SystemTime> {
+    Some(std::time::SystemTime::now())
+}
+
+/// Get the current time from JavaScript, as the standard clock panics on
+/// `wasm32-unknown-unknown`.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "js"))]
+fn now() -> Option<std::time::SystemTime> {
+    let millis = js_sys::Date::now() as u64;
+    Some(std::time::UNIX_EPOCH + std::time::Duration::from_millis(millis))
+}
+
+/// The current time isn't available on `wasm32-unknown-unknown` without the
+/// `js` feature.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "js")))]
+fn now() -> Option<std::time::SystemTime> {
+    None
+}
+
+/// Capture the backtrace of a new error, with the `backtrace` feature.
+fn creation_backtrace() -> Option<std::backtrace::Backtrace> {
+    #[cfg(feature = "backtrace")]
+    return crate::panic::capture_std_backtrace();
+    #[cfg(not(feature = "backtrace"))]
+    None
+}
+
+/// Get the attachments captured when an error is created: with the `tracing`
+/// feature, the trace and span IDs of the current span, to join the error to
+/// its trace, and with the `span-trace` feature, the trace of the spans
+/// entered. The backtrace is attached if there is one.
+fn creation_attachments(backtrace: Option<std::backtrace::Backtrace>) -> Option<Box<Attachments>> {
+    #[allow(unused_mut)]
+    let mut attachments: Option<Box<Attachments>> = None;
+    #[cfg(feature = "tracing")]
+    if let Some((trace_id, span_id)) = crate::tracing::current_trace_ids() {
+        let attachments = attachments.get_or_insert_with(Default::default);
+        attachments.trace_id = Some(trace_id);
+        attachments.span_id = Some(span_id);
+        #[cfg(feature = "span-trace")]
+        {
+            attachments.span_trace = Some(tracing_error::SpanTrace::capture())
+                .filter(|trace| trace.status() == tracing_error::SpanTraceStatus::CAPTURED);
+        }
+    }
+    if let Some(backtrace) = backtrace {
+        attachments.get_or_insert_with(Default::default).backtrace = Some(backtrace);
+    }
+    attachments
+}
+
+/// A simple error type that implements the [`ErrorStacks`] trait.
+///
+/// Its code type defaults to [`ErrorCode`]. Applications can use their own
+/// code type `C` instead, in which case errors are created with
+/// `StackError::<C>::default()` and the [`ErrorStacks`] builder methods:
+///
+/// ```rust
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// enum AppCode {
+///     QuotaExceeded,
+/// }
+///
+/// let error = StackError::<AppCode>::default()
+///     .with_err_msg("Quota exceeded")
+///     .with_err_code(AppCode::QuotaExceeded);
+/// ```
+///
+/// The features built on [`ErrorCode`], such as the coded constructors,
+/// conversions, and the origin and retryable classifications, are only
+/// available with the default code type.
+pub struct StackError<C = ErrorCode> {
+    message: Option<Message>,
+    source: Option<Box<StackError<C>>>,
+    code: Option<C>,
     uri: Option<String>,
+    fields: Vec<(String, String)>,
+    attachments: Option<Box<Attachments>>,
+    repeats: u32,
+    timestamp: Option<std::time::SystemTime>,
+    location: Option<&'static std::panic::Location<'static>>,
+}
+
+impl<C> Default for StackError<C> {
+    /// Creates an empty error carrying the ambient context fields, and the
+    /// trace of the current `tracing` span and backtrace if enabled.
+    fn default() -> Self {
+        Self {
+            message: None,
+            source: None,
+            code: None,
+            uri: None,
+            fields: context_fields(),
+            attachments: creation_attachments(creation_backtrace()),
+            repeats: 0,
+            timestamp: now(),
+            location: None,
+        }
+    }
 }
 
 impl StackError {
+    /// Creates a new StackError with the given message, as
+    /// [`StackError::from_msg`].
+    #[track_caller]
+    pub fn new(error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
+        Self::from_msg(error)
+    }
+
     /// Creates a new empty StackError.
-    pub fn new() -> Self {
+    pub fn empty() -> Self {
         Self::default()
     }
 
     /// Creates a new StackError from any error message that implements
     /// Display + Send + Sync.
+    #[track_caller]
     pub fn from_msg(error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
         Self {
-            message: Some(Box::new(error)),
+            message: Some(Message::Display(Box::new(error))),
+            location: Some(std::panic::Location::caller()),
             ..Default::default()
         }
     }
+
+    /// Creates a new StackError wrapping an error. The error's message is
+    /// used as the frame's message, and the error itself can be retrieved
+    /// with [`StackError::downcast_ref`].
+    #[track_caller]
+    pub fn from_err(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self {
+            message: Some(Message::Error(Box::new(error))),
+            location: Some(std::panic::Location::caller()),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a new StackError wrapping an error, with one frame per error
+    /// in its `source()` chain. The top frame wraps the error itself as with
+    /// [`StackError::from_err`].
+    #[track_caller]
+    pub fn from_err_chain(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        let causes: Vec<_> = std::iter::successors(error.source(), |e| e.source())
+            .map(|e| e.to_string())
+            .collect();
+        let source = causes.into_iter().rev().fold(None, |source, cause| {
+            Some(Box::new(Self {
+                message: Some(Message::Display(Box::new(cause))),
+                source,
+                code: None,
+                uri: None,
+                fields: Vec::new(),
+                attachments: None,
+                repeats: 0,
+                timestamp: now(),
+                location: None,
+            }))
+        });
+        Self {
+            message: Some(Message::Error(Box::new(error))),
+            source,
+            location: Some(std::panic::Location::caller()),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an error for a panic with the [`ErrorCode::RuntimePanic`]
+    /// code. The backtrace captured by the panic hook is attached instead of
+    /// capturing one, and there is no location, as it would be in this crate.
+    pub(crate) fn from_panic(
+        message: String,
+        backtrace: Option<std::backtrace::Backtrace>,
+    ) -> Self {
+        Self {
+            message: Some(Message::Display(Box::new(message))),
+            source: None,
+            code: Some(ErrorCode::RuntimePanic),
+            uri: None,
+            fields: context_fields(),
+            attachments: creation_attachments(backtrace),
+            repeats: 0,
+            timestamp: now(),
+            location: None,
+        }
+    }
+
+    /// Set whether the operation can be retried unchanged, as a hint: the
+    /// [`RecoveryHint::Retry`] hint if it can, unless the hint already is
+    /// [`RecoveryHint::RetryAfter`], or else the [`RecoveryHint::GiveUp`]
+    /// hint, unless the hint already is another hint which doesn't retry
+    /// unchanged, such as [`RecoveryHint::Reauthenticate`].
+    pub fn with_err_retryable(self, retryable: bool) -> Self {
+        match (retryable, self.err_hint()) {
+            (true, Some(RecoveryHint::Retry | RecoveryHint::RetryAfter(_))) => self,
+            (true, _) => self.with_err_hint(RecoveryHint::Retry),
+            (false, None | Some(RecoveryHint::Retry | RecoveryHint::RetryAfter(_))) => {
+                self.with_err_hint(RecoveryHint::GiveUp)
+            }
+            (false, _) => self,
+        }
+    }
+
+    /// Check if the operation can be retried unchanged. This is decided by
+    /// the hint if there is one: [`RecoveryHint::Retry`] and
+    /// [`RecoveryHint::RetryAfter`] are retryable, and other hints aren't.
+    /// Otherwise, errors with a transient code, see
+    /// [`ErrorCode::is_transient`], are retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self.err_hint() {
+            Some(hint) => matches!(hint, RecoveryHint::Retry | RecoveryHint::RetryAfter(_)),
+            None => self.code.is_some_and(ErrorCode::is_transient),
+        }
+    }
+
+    /// Get the origin of the error: the one set with
+    /// [`ErrorStacks::with_err_origin`], or else the one derived from the
+    /// code with [`ErrorOrigin::from_code`].
+    pub fn origin(&self) -> Option<ErrorOrigin> {
+        self.err_origin()
+            .copied()
+            .or_else(|| self.code.and_then(ErrorOrigin::from_code))
+    }
+
+    /// Get the process exit code for the error: the
+    /// [sysexits](ErrorCode::sysexit) status of its code, or 1 if it has no
+    /// code.
+    pub fn exit_code(&self) -> std::process::ExitCode {
+        self.code.map_or(1, ErrorCode::sysexit).into()
+    }
 }
 
-impl ErrorStacks<ErrorCode> for StackError {
-    fn err_code(&self) -> Option<&ErrorCode> {
+impl<C> StackError<C> {
+    /// Get a reference to the first error of type `T` wrapped with
+    /// [`StackError::from_err`], searching from the top frame to the root.
+    pub fn downcast_ref<T: std::error::Error + 'static>(&self) -> Option<&T> {
+        std::iter::successors(Some(self), |e| e.source.as_deref()).find_map(|e| match &e.message {
+            Some(Message::Error(error)) => error.downcast_ref::<T>(),
+            _ => None,
+        })
+    }
+
+    /// Attach a value of any type to the top frame. It can be retrieved with
+    /// [`StackError::get_attachment`], e.g. to pass structured recovery data
+    /// to the layer handling the error.
+    ///
+    /// Attached errors are rendered as branches of the frame, so that grouped
+    /// failures stay readable:
+    ///
+    /// ```rust
+    /// let error = failures.into_iter().fold(
+    ///     StackError::from_msg(format!("{} of {} uploads failed", count, total)),
+    ///     StackError::attach,
+    /// );
+    /// ```
+    pub fn attach<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.attachments_mut().values.push(Box::new(value));
+        self
+    }
+
+    /// Get a reference to the last value of type `T` attached with
+    /// [`StackError::attach`], searching from the top frame to the root.
+    pub fn get_attachment<T: 'static>(&self) -> Option<&T> {
+        std::iter::successors(Some(self), |e| e.source.as_deref()).find_map(|e| {
+            e.attachments
+                .as_ref()?
+                .values
+                .iter()
+                .rev()
+                .find_map(|value| value.downcast_ref::<T>())
+        })
+    }
+
+    /// Iterate over the values of type `T` attached to the top frame with
+    /// [`StackError::attach`], in the order in which they were attached.
+    pub fn frame_attachments<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.attachments
+            .iter()
+            .flat_map(|a| a.values.iter())
+            .filter_map(|value| value.downcast_ref::<T>())
+    }
+
+    /// Set the delay after which the operation can be retried, as the
+    /// [`RecoveryHint::RetryAfter`] hint.
+    pub fn with_retry_after(mut self, delay: std::time::Duration) -> Self {
+        self.attachments_mut().hint = Some(RecoveryHint::RetryAfter(delay));
+        self
+    }
+
+    /// Get the delay after which the operation can be retried, if the hint is
+    /// [`RecoveryHint::RetryAfter`].
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self.attachments.as_ref().and_then(|a| a.hint.as_ref()) {
+            Some(RecoveryHint::RetryAfter(delay)) => Some(*delay),
+            _ => None,
+        }
+    }
+
+    /// Get the ID of the distributed trace in which the error was created.
+    ///
+    /// With the `tracing` feature, it is captured when the error is created
+    /// inside a span: it is the `trace_id` field of the closest span recording
+    /// one with [`SpanFieldsLayer`](crate::tracing::SpanFieldsLayer), or else
+    /// the ID of the root span. Frames stacked on the error inherit it.
+    pub fn err_trace_id(&self) -> Option<&str> {
+        self.attachments
+            .as_ref()
+            .and_then(|a| a.trace_id.as_deref())
+    }
+
+    /// Get the ID of the `tracing` span in which the error was created.
+    pub fn err_span_id(&self) -> Option<u64> {
+        self.attachments.as_ref().and_then(|a| a.span_id)
+    }
+
+    /// Get the trace of the `tracing` spans entered when the error was
+    /// created, searching from the top frame to the root. Spans are only
+    /// captured if the subscriber has a `tracing_error::ErrorLayer`.
+    #[cfg(feature = "span-trace")]
+    pub fn err_span_trace(&self) -> Option<&tracing_error::SpanTrace> {
+        std::iter::successors(Some(self), |e| e.source.as_deref())
+            .find_map(|e| e.attachments.as_ref()?.span_trace.as_ref())
+    }
+
+    /// Get the backtrace captured when the error was created with the
+    /// `backtrace` feature, or by the panic hook for errors created from
+    /// panics, searching from the top frame to the root. Backtraces are
+    /// captured if enabled in the
+    /// [`StackErrorConfig`](crate::config::StackErrorConfig), or else with the
+    /// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables.
+    pub fn err_backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        std::iter::successors(Some(self), |e| e.source.as_deref())
+            .find_map(|e| e.attachments.as_ref()?.backtrace.as_ref())
+    }
+
+    /// Set the trace and span IDs, e.g. for errors received from another
+    /// service.
+    pub fn with_err_trace(mut self, trace_id: impl Into<String>, span_id: Option<u64>) -> Self {
+        let attachments = self.attachments_mut();
+        attachments.trace_id = Some(trace_id.into());
+        attachments.span_id = span_id;
+        self
+    }
+
+    /// Remove the trace and span IDs.
+    pub fn with_no_err_trace(mut self) -> Self {
+        if let Some(attachments) = &mut self.attachments {
+            attachments.trace_id = None;
+            attachments.span_id = None;
+        }
+        self
+    }
+
+    /// Get the attachments inherited by a frame stacked on this one.
+    fn inherited_attachments(&self) -> Option<Box<Attachments>> {
+        let attachments = self.attachments.as_ref()?;
+        let inherited = Attachments {
+            hint: attachments.hint,
+            origin: attachments.origin,
+            severity: attachments.severity,
+            trace_id: attachments.trace_id.clone(),
+            span_id: attachments.span_id,
+            ..Default::default()
+        };
+        let inherits = inherited.hint.is_some()
+            || inherited.origin.is_some()
+            || inherited.severity.is_some()
+            || inherited.trace_id.is_some();
+        inherits.then(|| Box::new(inherited))
+    }
+
+    fn attachments_mut(&mut self) -> &mut Attachments {
+        self.attachments.get_or_insert_with(Default::default)
+    }
+
+    /// Drop the frames above the root until there are at most `max_depth`
+    /// frames, keeping at least the root and the top frames.
+    fn truncate_depth(mut self, max_depth: usize) -> Self {
+        let mut depth = std::iter::successors(Some(&self), |e| e.source.as_deref()).count();
+        while depth > max_depth.max(2) {
+            // Find the frame stacked on the one above the root
+            let mut frame = &mut self;
+            while frame
+                .source
+                .as_ref()
+                .and_then(|s| s.source.as_ref())
+                .is_some_and(|s| s.source.is_some())
+            {
+                frame = frame.source.as_deref_mut().expect("checked above");
+            }
+            let dropped = frame.source.take().expect("depth is above 2");
+            frame.source = dropped.source;
+            depth -= 1;
+        }
+        self
+    }
+
+    /// Apply the maximum depth of the process-wide
+    /// [`StackErrorConfig`](crate::config::StackErrorConfig).
+    fn limit_depth(self) -> Self {
+        match crate::config::config().max_depth {
+            Some(max_depth) => self.truncate_depth(max_depth),
+            None => self,
+        }
+    }
+
+    /// Get the number of times the top frame was stacked with
+    /// [`StackError::stack_or_repeat_err_msg`]. It is shown in the `Debug`
+    /// output.
+    pub fn repeat_count(&self) -> usize {
+        self.repeats as usize + 1
+    }
+
+    /// Set the number of times the top frame was stacked, e.g. for frames
+    /// decoded from another process. Counts below 1 are treated as 1.
+    pub fn with_repeat_count(self, count: usize) -> Self {
+        Self {
+            repeats: u32::try_from(count.saturating_sub(1)).unwrap_or(u32::MAX),
+            ..self
+        }
+    }
+
+    /// Stack a new error with the given message, unless the top frame has the
+    /// same message, in which case its repeat count is incremented instead.
+    /// This keeps chains concise when stacking in a loop, e.g. when retrying.
+    /// The repeated frame keeps the location at which it was first stacked.
+    #[track_caller]
+    pub fn stack_or_repeat_err_msg(
+        mut self,
+        message: impl std::fmt::Display + Send + Sync + 'static,
+    ) -> Self
+    where
+        C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    {
+        if let Some(previous) = &self.message {
+            if previous.to_string() == message.to_string() {
+                self.repeats = self.repeats.saturating_add(1);
+                return self;
+            }
+        }
+        self.stack_err_msg(message)
+    }
+
+    /// Get the location in the source code at which the top frame was
+    /// created or given its message. It is recorded by the constructors
+    /// taking a message, and by [`ErrorStacks::with_err_msg`],
+    /// [`ErrorStacks::stack_err`] and [`ErrorStacks::stack_err_msg`].
+    pub fn err_location(&self) -> Option<&'static std::panic::Location<'static>> {
+        self.location
+    }
+
+    /// Remove the location of the top frame, e.g. for frames decoded from
+    /// another process, as their location isn't known.
+    pub fn with_no_err_location(self) -> Self {
+        Self {
+            location: None,
+            ..self
+        }
+    }
+
+    /// Remove what was captured locally when the top frame was created: its
+    /// timestamp, backtrace and span trace, e.g. for frames decoded from
+    /// another process, as they would describe the decoder instead.
+    pub fn with_no_err_capture(mut self) -> Self {
+        if let Some(attachments) = &mut self.attachments {
+            attachments.backtrace = None;
+            #[cfg(feature = "span-trace")]
+            {
+                attachments.span_trace = None;
+            }
+        }
+        Self {
+            timestamp: None,
+            ..self
+        }
+    }
+
+    /// Get the time at which the top frame was created. It isn't recorded on
+    /// `wasm32-unknown-unknown` unless the `js` feature is enabled.
+    pub fn timestamp(&self) -> Option<std::time::SystemTime> {
+        self.timestamp
+    }
+
+    /// Iterate over the errors in the causal chain, starting with this
+    /// error. The root frame is followed by the `source()` chain of the error
+    /// it wraps, if it was created with [`StackError::from_err`].
+    pub fn iter_sources(&self) -> impl Iterator<Item = &(dyn std::error::Error + 'static)>
+    where
+        C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+    {
+        std::iter::successors(Some(self), |e| e.source.as_deref()).flat_map(|frame| {
+            // Frames wrapping an error with sources are only roots, unless
+            // the sources were already flattened with `from_err_chain`
+            let wrapped = match (&frame.message, &frame.source) {
+                (Some(Message::Error(error)), None) => error.source(),
+                _ => None,
+            };
+            std::iter::once(frame as &(dyn std::error::Error + 'static))
+                .chain(std::iter::successors(wrapped, |e| e.source()))
+        })
+    }
+
+    /// Get the message of the top frame. Unlike `Display`, this doesn't go
+    /// through the [renderer](crate::render::ErrorRenderer), so renderers
+    /// can use it to get the raw message of each frame.
+    pub fn frame_msg(&self) -> impl std::fmt::Display + '_ {
+        struct FrameMsg<'a>(Option<&'a Message>);
+
+        impl std::fmt::Display for FrameMsg<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self.0 {
+                    Some(message) => message.fmt(f),
+                    None => Ok(()),
+                }
+            }
+        }
+
+        FrameMsg(self.message.as_ref())
+    }
+
+    /// Iterate over the codes set along the chain, from the top frame to the
+    /// root. Frames inherit the code of the frame they are stacked on, so the
+    /// code of a frame is only included if it differs from the code of the
+    /// frame below it. This keeps the original classification of errors
+    /// which were re-tagged by intermediate layers, while
+    /// [`ErrorStacks::err_code`] gets the code of the top frame.
+    pub fn err_codes(&self) -> impl Iterator<Item = &C>
+    where
+        C: PartialEq,
+    {
+        std::iter::successors(Some(self), |e| e.source.as_deref()).filter_map(|frame| {
+            let inherited = frame.source.as_ref().map(|source| source.code.as_ref());
+            match &frame.code {
+                Some(code) if inherited != Some(Some(code)) => Some(code),
+                _ => None,
+            }
+        })
+    }
+
+    /// Check if the message of any frame contains the given string.
+    pub fn contains_msg(&self, needle: &str) -> bool {
+        std::iter::successors(Some(self), |e| e.source.as_deref())
+            .any(|frame| frame.frame_msg().to_string().contains(needle))
+    }
+
+    /// Get a fingerprint identifying the kind of error, computed from the
+    /// messages and codes of its frames.
+    ///
+    /// The fingerprint is stable across processes and builds, so it can be
+    /// used to group identical errors in logs or between services.
+    pub fn err_fingerprint(&self) -> u64
+    where
+        C: std::fmt::Debug,
+    {
+        // FNV-1a, as the std hashers aren't guaranteed to be stable
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut write = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+        for frame in std::iter::successors(Some(self), |e| e.source.as_deref()) {
+            write(frame.frame_msg().to_string().as_bytes());
+            write(&[0]);
+            if let Some(code) = &frame.code {
+                write(format!("{code:?}").as_bytes());
+            }
+            write(&[0]);
+        }
+        hash
+    }
+
+    /// Splits the error into its frames, ordered from the root to the top.
+    /// Each frame is returned without its source.
+    pub fn into_frames(self) -> Vec<StackError<C>> {
+        let mut frames = Vec::new();
+        let mut next = Some(self);
+        while let Some(mut frame) = next {
+            next = frame.source.take().map(|source| *source);
+            frames.push(frame);
+        }
+        frames.reverse();
+        frames
+    }
+}
+
+/// Generates constructors creating an error from a message with a code.
+macro_rules! coded_constructors {
+    ($($name:ident => $code:ident,)*) => {
+        impl StackError {
+            $(
+                #[doc = concat!(
+                    "Creates a new StackError from a message, with the [`ErrorCode::",
+                    stringify!($code),
+                    "`] code."
+                )]
+                #[track_caller]
+                pub fn $name(error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
+                    Self::from_msg(error).with_err_code(ErrorCode::$code)
+                }
+            )*
+        }
+    };
+}
+
+coded_constructors! {
+    invalid_value => RuntimeInvalidValue,
+    invalid_index => RuntimeInvalidIndex,
+    invalid_key => RuntimeInvalidKey,
+    not_implemented => RuntimeNotImplemented,
+    bad_request => HttpBadRequest,
+    unauthorized => HttpUnauthorized,
+    forbidden => HttpForbidden,
+    not_found => HttpNotFound,
+    conflict => HttpConflict,
+    too_many_requests => HttpTooManyRequests,
+    internal => HttpInternalServerError,
+    unavailable => HttpServiceUnavailable,
+    already_exists => IoAlreadyExists,
+    timeout => IoTimedOut,
+}
+
+impl<C> ErrorStacks<C> for StackError<C>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+{
+    fn err_code(&self) -> Option<&C> {
         self.code.as_ref()
     }
 
-    fn with_err_code(self, code: ErrorCode) -> Self {
+    fn with_err_code(self, code: C) -> Self {
         Self {
             code: Some(code),
             ..self
@@ -134,9 +990,130 @@ impl ErrorStacks<ErrorCode> for StackError {
         Self { uri: None, ..self }
     }
 
+    fn err_fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    fn with_err_field(mut self, key: impl Into<String>, value: impl std::fmt::Display) -> Self {
+        self.fields.push((key.into(), value.to_string()));
+        self
+    }
+
+    fn with_no_err_fields(self) -> Self {
+        Self {
+            fields: Vec::new(),
+            ..self
+        }
+    }
+
+    fn err_spans(&self) -> &[ErrorSpan] {
+        self.attachments.as_ref().map_or(&[], |a| &a.spans)
+    }
+
+    fn with_err_span(
+        mut self,
+        source_name: impl Into<String>,
+        range: std::ops::Range<usize>,
+    ) -> Self {
+        self.attachments_mut()
+            .spans
+            .push(ErrorSpan::new(source_name, range));
+        self
+    }
+
+    fn with_no_err_spans(mut self) -> Self {
+        if let Some(attachments) = &mut self.attachments {
+            attachments.spans.clear();
+        }
+        self
+    }
+
+    fn err_position(&self) -> Option<&ErrorPosition> {
+        self.attachments.as_ref().and_then(|a| a.position.as_ref())
+    }
+
+    fn with_err_position(mut self, offset: usize, line: usize, column: usize) -> Self {
+        self.attachments_mut().position = Some(ErrorPosition::new(offset, line, column));
+        self
+    }
+
+    fn with_no_err_position(mut self) -> Self {
+        if let Some(attachments) = &mut self.attachments {
+            attachments.position = None;
+        }
+        self
+    }
+
+    fn err_hint(&self) -> Option<&RecoveryHint> {
+        self.attachments.as_ref().and_then(|a| a.hint.as_ref())
+    }
+
+    fn with_err_hint(mut self, hint: RecoveryHint) -> Self {
+        self.attachments_mut().hint = Some(hint);
+        self
+    }
+
+    fn with_no_err_hint(mut self) -> Self {
+        if let Some(attachments) = &mut self.attachments {
+            attachments.hint = None;
+        }
+        self
+    }
+
+    fn err_origin(&self) -> Option<&ErrorOrigin> {
+        self.attachments.as_ref().and_then(|a| a.origin.as_ref())
+    }
+
+    fn with_err_origin(mut self, origin: ErrorOrigin) -> Self {
+        self.attachments_mut().origin = Some(origin);
+        self
+    }
+
+    fn with_no_err_origin(mut self) -> Self {
+        if let Some(attachments) = &mut self.attachments {
+            attachments.origin = None;
+        }
+        self
+    }
+
+    fn err_severity(&self) -> Option<&Severity> {
+        self.attachments.as_ref().and_then(|a| a.severity.as_ref())
+    }
+
+    fn with_err_severity(mut self, severity: Severity) -> Self {
+        self.attachments_mut().severity = Some(severity);
+        self
+    }
+
+    fn with_no_err_severity(mut self) -> Self {
+        if let Some(attachments) = &mut self.attachments {
+            attachments.severity = None;
+        }
+        self
+    }
+
+    fn err_elapsed(&self) -> Option<std::time::Duration> {
+        self.attachments.as_ref().and_then(|a| a.elapsed)
+    }
+
+    fn with_err_elapsed(mut self, elapsed: std::time::Duration) -> Self {
+        self.attachments_mut().elapsed = Some(elapsed);
+        self
+    }
+
+    fn err_deadline(&self) -> Option<std::time::Instant> {
+        self.attachments.as_ref().and_then(|a| a.deadline)
+    }
+
+    fn with_err_deadline(mut self, deadline: std::time::Instant) -> Self {
+        self.attachments_mut().deadline = Some(deadline);
+        self
+    }
+
     fn with_err_msg(self, message: impl std::fmt::Display + Send + Sync + 'static) -> Self {
         Self {
-            message: Some(Box::new(message)),
+            message: Some(Message::Display(Box::new(message))),
+            location: Some(std::panic::Location::caller()),
             ..self
         }
     }
@@ -149,61 +1126,138 @@ impl ErrorStacks<ErrorCode> for StackError {
     }
 
     fn stack_err(self) -> Self {
-        let code = self.code;
+        let code = self.code.clone();
         let uri = self.uri.clone();
-        Self {
+        let attachments = self.inherited_attachments();
+        let stacked = Self {
             message: None,
             source: Some(Box::new(self)),
             code,
             uri,
-        }
+            fields: Vec::new(),
+            attachments,
+            repeats: 0,
+            timestamp: now(),
+            location: Some(std::panic::Location::caller()),
+        };
+        stacked.limit_depth()
     }
 
     fn stack_err_msg(self, message: impl std::fmt::Display + Send + Sync + 'static) -> Self {
-        let code = self.code;
+        let code = self.code.clone();
         let uri = self.uri.clone();
-        Self {
-            message: Some(Box::new(message)),
+        let attachments = self.inherited_attachments();
+        let stacked = Self {
+            message: Some(Message::Display(Box::new(message))),
             source: Some(Box::new(self)),
             code,
             uri,
+            fields: Vec::new(),
+            attachments,
+            repeats: 0,
+            timestamp: now(),
+            location: Some(std::panic::Location::caller()),
+        };
+        stacked.limit_depth()
+    }
+
+    fn with_err_location(self, location: &'static std::panic::Location<'static>) -> Self {
+        Self {
+            location: Some(location),
+            ..self
         }
     }
 }
 
-impl std::fmt::Display for StackError {
+// Errors with other code types can't be passed to the renderer, so they are
+// rendered as with the default renderer
+impl<C> std::fmt::Display for StackError<C>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+{
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match &self.message {
-            Some(error) => {
-                write!(f, "{}", error)
-            }
-            None => Ok(()),
+        match (self as &dyn std::any::Any).downcast_ref::<StackError>() {
+            Some(error) => crate::render::renderer().display(error, f),
+            None => write!(f, "{}", self.frame_msg()),
         }
     }
 }
 
-impl std::fmt::Debug for StackError {
+impl<C> std::fmt::Debug for StackError<C>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (idx, err) in std::iter::successors(Some(self), |e| e.source.as_deref())
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .enumerate()
-        {
-            if idx > 0 {
-                writeln!(f)?;
-            }
-            write!(f, "{err}")?;
+        match (self as &dyn std::any::Any).downcast_ref::<StackError>() {
+            Some(error) => crate::render::renderer().debug(error, f),
+            None => crate::render::fmt_error(self, crate::render::verbosity(), f),
         }
-        Ok(())
     }
 }
 
-impl std::error::Error for StackError {
+impl<C> std::error::Error for StackError<C>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+{
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self.source {
             Some(source) => Some(source.as_ref()),
             None => None,
         }
     }
+
+    /// Provides the code, fields, spans and position of the frame, and
+    /// forwards the request to the wrapped error. Requires a nightly compiler
+    /// and `--cfg stackerror_nightly`.
+    #[cfg(stackerror_nightly)]
+    fn provide<'a>(&'a self, request: &mut std::error::Request<'a>) {
+        if let Some(code) = &self.code {
+            request.provide_ref::<C>(code);
+        }
+        request
+            .provide_ref::<[(String, String)]>(&self.fields)
+            .provide_ref::<[ErrorSpan]>(self.err_spans());
+        if let Some(position) = self.err_position() {
+            request.provide_ref::<ErrorPosition>(position);
+        }
+        if let Some(Message::Error(error)) = &self.message {
+            error.provide(request);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_depth_keeps_root_and_top() {
+        let error = StackError::from_msg("Root error")
+            .stack_err_msg("First error")
+            .stack_err_msg("Second error")
+            .stack_err_msg("Top error")
+            .truncate_depth(3);
+        let messages: Vec<_> = std::iter::successors(Some(&error), |e| e.source.as_deref())
+            .map(|e| e.frame_msg().to_string())
+            .collect();
+        assert_eq!(messages, ["Top error", "Second error", "Root error"]);
+
+        let error = error.truncate_depth(0);
+        assert_eq!(
+            std::iter::successors(Some(&error), |e| e.source.as_deref()).count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_backtrace_in_alternate_debug() {
+        let mut error = StackError::from_msg("Base error");
+        error.attachments_mut().backtrace = Some(std::backtrace::Backtrace::force_capture());
+        let error = error.stack_err_msg("Stacked error");
+        assert!(error.err_backtrace().is_some());
+        assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+        assert!(
+            format!("{:#?}", error).starts_with("Base error\nStacked error\n\nstack backtrace:\n")
+        );
+    }
 }
@@ -0,0 +1,66 @@
+//! Provides an `eyre` handler which renders stacked errors.
+
+use crate::error::{ErrorStacks, StackError};
+
+/// An [`eyre::EyreHandler`] which renders the chain of a report in the same
+/// order as [`StackError`]'s `Debug` output, followed by the error code and
+/// URI of the first [`StackError`] in the chain.
+///
+/// The [`StackError`] itself is kept in the report, so its code and URI can
+/// still be retrieved with `report.downcast_ref::<StackError>()`.
+#[derive(Debug, Default)]
+pub struct StackErrorHandler;
+
+impl ::eyre::EyreHandler for StackErrorHandler {
+    fn debug(
+        &self,
+        error: &(dyn std::error::Error + 'static),
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        let chain: Vec<_> = std::iter::successors(Some(error), |e| e.source()).collect();
+        for (idx, err) in chain.iter().rev().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{err}")?;
+        }
+        if let Some(stack_error) = chain.iter().find_map(|e| e.downcast_ref::<StackError>()) {
+            if let Some(code) = stack_error.err_code() {
+                write!(f, "\n\ncode: {code:?}")?;
+            }
+            if let Some(uri) = stack_error.err_uri() {
+                write!(f, "\nuri: {uri}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Install [`StackErrorHandler`] as the global `eyre` report handler.
+pub fn install_eyre_hook() -> Result<(), ::eyre::InstallError> {
+    ::eyre::set_hook(Box::new(|_| Box::new(StackErrorHandler)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_report_renders_stack() {
+        install_eyre_hook().unwrap();
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_uri("https://example.com/base".to_string())
+            .stack_err_msg("Stacked error");
+        let report = ::eyre::Report::new(error).wrap_err("Report context");
+        assert_eq!(
+            format!("{:?}", report),
+            "Base error\nStacked error\nReport context\n\ncode: IoNotFound\nuri: https://example.com/base"
+        );
+        assert_eq!(
+            report.downcast_ref::<StackError>().unwrap().err_code(),
+            Some(&ErrorCode::IoNotFound)
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Provides the [`FluentRenderer`] for localizing error messages with
+//! [Fluent](https://projectfluent.org) bundles.
+//!
+//! Messages are used as Fluent message ids, such as `user-not-found`, and the
+//! fields of the error are the arguments of the message. Only the `Display`
+//! output is localized, so that the `Debug` output used in logs keeps the
+//! canonical ids.
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+
+use crate::error::{ErrorStacks, StackError};
+use crate::render::ErrorRenderer;
+
+/// An [`ErrorRenderer`] which localizes the `Display` output with a Fluent
+/// bundle. Messages which aren't in the bundle are rendered unchanged.
+///
+/// ```rust
+/// let mut bundle = FluentBundle::new_concurrent(vec![langid!("fr")]);
+/// bundle.add_resource(FluentResource::try_new(ftl).unwrap()).unwrap();
+/// stackerror::set_renderer(FluentRenderer::new(bundle));
+/// ```
+pub struct FluentRenderer {
+    bundle: FluentBundle<FluentResource>,
+}
+
+impl FluentRenderer {
+    /// Creates a renderer using the given bundle.
+    pub fn new(bundle: FluentBundle<FluentResource>) -> Self {
+        Self { bundle }
+    }
+
+    /// Localize the message of the top frame, or return it unchanged if it
+    /// isn't in the bundle.
+    ///
+    /// The fields of all frames are passed as arguments, those of the top
+    /// frames taking precedence. Numeric values are passed as numbers, so
+    /// that they can select plural forms.
+    pub fn localize(&self, error: &StackError) -> String {
+        let id = error.frame_msg().to_string();
+        let Some(pattern) = self.bundle.get_message(&id).and_then(|m| m.value()) else {
+            return id;
+        };
+        let mut frames: Vec<_> = std::iter::successors(Some(error), |e| {
+            std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+        })
+        .collect();
+        frames.reverse();
+        let mut args = FluentArgs::new();
+        for (key, value) in frames.iter().flat_map(|frame| frame.err_fields()) {
+            let value = match value.parse::<f64>() {
+                Ok(number) => FluentValue::from(number),
+                Err(_) => FluentValue::from(value.clone()),
+            };
+            args.set(key.clone(), value);
+        }
+        let mut errors = Vec::new();
+        self.bundle
+            .format_pattern(pattern, Some(&args), &mut errors)
+            .into_owned()
+    }
+}
+
+impl ErrorRenderer for FluentRenderer {
+    fn display(&self, error: &StackError, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.localize(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renderer() -> FluentRenderer {
+        let ftl = "user-not-found = L'utilisateur { $id } est introuvable\n\
+                   retries = { $count ->\n    [one] Une tentative\n   *[other] { $count } tentatives\n}\n";
+        let mut bundle = FluentBundle::new_concurrent(vec!["fr".parse().unwrap()]);
+        bundle.set_use_isolating(false);
+        bundle
+            .add_resource(FluentResource::try_new(ftl.to_string()).unwrap())
+            .unwrap();
+        FluentRenderer::new(bundle)
+    }
+
+    #[test]
+    fn test_fluent_localizes_messages() {
+        let renderer = renderer();
+        let error = StackError::from_msg("user-not-found").with_err_field("id", "alice");
+        assert_eq!(
+            renderer.localize(&error),
+            "L'utilisateur alice est introuvable"
+        );
+        let error = StackError::from_msg("Base error")
+            .with_err_field("count", 1)
+            .stack_err_msg("retries");
+        assert_eq!(renderer.localize(&error), "Une tentative");
+        let error = StackError::from_msg("unknown-id");
+        assert_eq!(renderer.localize(&error), "unknown-id");
+    }
+}
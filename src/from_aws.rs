@@ -0,0 +1,132 @@
+//! Conversions from AWS SDK errors into `StackError`.
+
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+use aws_smithy_runtime_api::client::result::SdkError;
+use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::hint::RecoveryHint;
+use crate::mapper::code_mapper;
+
+/// Service error codes used by AWS services for throttling.
+const THROTTLING_CODES: &[&str] = &[
+    "Throttling",
+    "ThrottlingException",
+    "ThrottledException",
+    "RequestThrottledException",
+    "TooManyRequestsException",
+    "ProvisionedThroughputExceededException",
+    "TransactionInProgressException",
+    "RequestLimitExceeded",
+    "BandwidthLimitExceeded",
+    "LimitExceededException",
+    "RequestThrottled",
+    "SlowDown",
+    "PriorRequestNotComplete",
+    "EC2ThrottledException",
+];
+
+/// Converts the `SdkError` of any `aws_sdk_*` operation.
+///
+/// Service errors are mapped from their HTTP status, and throttling errors
+/// have the `HttpTooManyRequests` code. Timeouts have the `IoTimedOut` code.
+/// Throttling, timeouts, dispatch failures and server errors have the
+/// [`RecoveryHint::Retry`] hint. The service error code and the request ID are
+/// set as the `aws_code` and `request_id` fields.
+impl<E> From<SdkError<E, HttpResponse>> for StackError
+where
+    E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+{
+    fn from(error: SdkError<E, HttpResponse>) -> Self {
+        let status = error.raw_response().map(|raw| raw.status().as_u16());
+        let aws_code = error
+            .as_service_error()
+            .and_then(|e| e.code())
+            .map(str::to_string);
+        let request_id = error
+            .raw_response()
+            .and_then(|raw| {
+                raw.headers()
+                    .get("x-amzn-requestid")
+                    .or_else(|| raw.headers().get("x-amz-request-id"))
+            })
+            .or_else(|| error.as_service_error()?.meta().extra("aws_request_id"))
+            .map(str::to_string);
+        let throttled = aws_code
+            .as_deref()
+            .is_some_and(|code| THROTTLING_CODES.contains(&code));
+        let (code, hint) = match &error {
+            SdkError::TimeoutError(_) => (Some(ErrorCode::IoTimedOut), Some(RecoveryHint::Retry)),
+            SdkError::DispatchFailure(failure) if failure.is_timeout() => {
+                (Some(ErrorCode::IoTimedOut), Some(RecoveryHint::Retry))
+            }
+            SdkError::DispatchFailure(failure) if failure.is_io() => {
+                (Some(ErrorCode::IoNotConnected), Some(RecoveryHint::Retry))
+            }
+            SdkError::ConstructionFailure(_) => (Some(ErrorCode::IoInvalidInput), None),
+            SdkError::ResponseError(_) => (Some(ErrorCode::IoInvalidData), None),
+            _ if throttled => (
+                Some(ErrorCode::HttpTooManyRequests),
+                Some(RecoveryHint::Retry),
+            ),
+            _ => (
+                status.and_then(|status| code_mapper().map_http_status(status)),
+                status
+                    .filter(|status| *status >= 500)
+                    .map(|_| RecoveryHint::Retry),
+            ),
+        };
+        let mut stacked = StackError::from_err(error);
+        if let Some(code) = code {
+            stacked = stacked.with_err_code(code);
+        }
+        if let Some(hint) = hint {
+            stacked = stacked.with_err_hint(hint);
+        }
+        if let Some(aws_code) = aws_code {
+            stacked = stacked.with_err_field("aws_code", aws_code);
+        }
+        if let Some(request_id) = request_id {
+            stacked = stacked.with_err_field("request_id", request_id);
+        }
+        stacked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_smithy_runtime_api::http::StatusCode;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::error::ErrorMetadata;
+
+    fn service_error(status: u16, code: &str) -> SdkError<ErrorMetadata, HttpResponse> {
+        let mut raw = HttpResponse::new(StatusCode::try_from(status).unwrap(), SdkBody::empty());
+        raw.headers_mut().insert("x-amzn-requestid", "req-1");
+        SdkError::service_error(ErrorMetadata::builder().code(code).build(), raw)
+    }
+
+    #[test]
+    fn test_aws_error_classification() {
+        let error = StackError::from(service_error(400, "ThrottlingException"));
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpTooManyRequests));
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::Retry));
+        assert_eq!(
+            error.err_fields(),
+            &[
+                ("aws_code".to_string(), "ThrottlingException".to_string()),
+                ("request_id".to_string(), "req-1".to_string())
+            ]
+        );
+
+        let error = StackError::from(service_error(404, "NoSuchKey"));
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpNotFound));
+        assert_eq!(error.err_hint(), None);
+
+        let error = StackError::from(SdkError::<ErrorMetadata, HttpResponse>::timeout_error(
+            "timed out",
+        ));
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoTimedOut));
+    }
+}
@@ -0,0 +1,94 @@
+//! Conversions from `config` errors into `StackError`.
+
+use config::ConfigError;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+/// Missing keys have the `IoNotFound` code, and values which can't be parsed
+/// or converted have the `IoInvalidData` code. The key, origin and expected
+/// type of the value are set as fields when known.
+impl From<ConfigError> for StackError {
+    fn from(error: ConfigError) -> Self {
+        let mut code = None;
+        let mut fields = Vec::new();
+        let mut current = &error;
+        loop {
+            match current {
+                ConfigError::NotFound(key) => {
+                    code = Some(ErrorCode::IoNotFound);
+                    fields.push(("key", key.clone()));
+                }
+                ConfigError::Type {
+                    origin,
+                    expected,
+                    key,
+                    ..
+                } => {
+                    code = Some(ErrorCode::IoInvalidData);
+                    fields.extend(key.clone().map(|key| ("key", key)));
+                    fields.extend(origin.clone().map(|origin| ("origin", origin)));
+                    fields.push(("expected", expected.to_string()));
+                }
+                ConfigError::FileParse { uri, .. } => {
+                    code = Some(ErrorCode::IoInvalidData);
+                    fields.extend(uri.clone().map(|uri| ("origin", uri)));
+                }
+                ConfigError::PathParse { .. } => code = Some(ErrorCode::IoInvalidInput),
+                ConfigError::At { error, origin, key } => {
+                    fields.extend(key.clone().map(|key| ("key", key)));
+                    fields.extend(origin.clone().map(|origin| ("origin", origin)));
+                    current = error;
+                    continue;
+                }
+                _ => {}
+            }
+            break;
+        }
+        // Keep the outermost value of each field
+        let mut seen = Vec::new();
+        fields.retain(|(key, _)| {
+            let first = !seen.contains(key);
+            seen.push(*key);
+            first
+        });
+        let mut stacked = StackError::from_err(error);
+        if let Some(code) = code {
+            stacked = stacked.with_err_code(code);
+        }
+        fields.into_iter().fold(stacked, |stacked, (key, value)| {
+            stacked.with_err_field(key, value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(key: &str, value: &str) -> config::Config {
+        config::Config::builder()
+            .set_override(key, value)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_config_error_codes() {
+        let error = StackError::from(ConfigError::NotFound("port".to_string()));
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+        assert_eq!(
+            error.err_fields(),
+            &[("key".to_string(), "port".to_string())]
+        );
+
+        let error = StackError::from(load("port", "x").get::<u16>("port").unwrap_err());
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidData));
+        assert_eq!(
+            error.err_fields()[0],
+            ("key".to_string(), "port".to_string())
+        );
+        assert!(error.downcast_ref::<ConfigError>().is_some());
+    }
+}
@@ -0,0 +1,54 @@
+//! Conversions from `dotenvy` errors into `StackError`.
+
+use std::env::VarError;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::mapper::code_mapper;
+
+/// I/O errors, such as a missing env file, are mapped by kind as
+/// `io::Error` is. Lines which can't be parsed have the `IoInvalidData`
+/// code, with the line and the index of the error as fields. Missing
+/// variables have the `IoNotFound` code.
+impl From<dotenvy::Error> for StackError {
+    fn from(error: dotenvy::Error) -> Self {
+        let (code, fields) = match &error {
+            dotenvy::Error::Io(io) => (code_mapper().map_io_kind(io.kind()), Vec::new()),
+            dotenvy::Error::LineParse(line, index) => (
+                Some(ErrorCode::IoInvalidData),
+                vec![("line", line.clone()), ("index", index.to_string())],
+            ),
+            dotenvy::Error::EnvVar(VarError::NotPresent) => {
+                (Some(ErrorCode::IoNotFound), Vec::new())
+            }
+            dotenvy::Error::EnvVar(VarError::NotUnicode(_)) => {
+                (Some(ErrorCode::IoInvalidData), Vec::new())
+            }
+            _ => (None, Vec::new()),
+        };
+        let mut stacked = StackError::from_err(error);
+        if let Some(code) = code {
+            stacked = stacked.with_err_code(code);
+        }
+        fields.into_iter().fold(stacked, |stacked, (key, value)| {
+            stacked.with_err_field(key, value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dotenvy_error_codes() {
+        let error = StackError::from(dotenvy::from_filename("missing.env").unwrap_err());
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+
+        let mut lines = dotenvy::from_read_iter("KEY=value\n'invalid\n".as_bytes());
+        assert!(lines.next().unwrap().is_ok());
+        let error = StackError::from(lines.next().unwrap().unwrap_err());
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidData));
+        assert_eq!(error.err_fields()[0].0, "line");
+    }
+}
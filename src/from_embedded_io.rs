@@ -0,0 +1,30 @@
+//! Conversions from `embedded-io` errors into `StackError`.
+
+use crate::error::{ErrorStacks, StackError};
+
+/// The kind is kept as the wrapped error, and is mapped to a code of the IO
+/// family with [`ErrorCode::from_embedded_io_kind`].
+///
+/// [`ErrorCode::from_embedded_io_kind`]: crate::codes::ErrorCode::from_embedded_io_kind
+impl From<embedded_io::ErrorKind> for StackError {
+    fn from(kind: embedded_io::ErrorKind) -> Self {
+        let err = StackError::from_err(kind);
+        match crate::codes::ErrorCode::from_embedded_io_kind(kind) {
+            Some(code) => err.with_err_code(code),
+            None => err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_embedded_io_kind_converts() {
+        let error = StackError::from(embedded_io::ErrorKind::ConnectionReset);
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoConnectionReset));
+        assert!(error.downcast_ref::<embedded_io::ErrorKind>().is_some());
+    }
+}
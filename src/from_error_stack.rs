@@ -0,0 +1,110 @@
+//! Conversions between `error_stack::Report` and `StackError`.
+
+use ::error_stack::{AttachmentKind, FrameKind, Report};
+
+use crate::error::{ErrorStacks, StackError};
+
+/// Converts each context of the report into a frame, starting from the root.
+/// `(String, String)` attachments, as added by [`StackError::into_report`],
+/// are added as fields on the frame of the context they are attached to, and
+/// other printable attachments as `attachment` fields. The code and URI of
+/// contexts which are themselves a `StackError` are kept.
+impl<C: ?Sized> From<Report<C>> for StackError {
+    fn from(report: Report<C>) -> Self {
+        let frames: Vec<_> = report.frames().collect();
+        let mut error: Option<StackError> = None;
+        for frame in frames.into_iter().rev() {
+            match frame.kind() {
+                FrameKind::Context(context) => {
+                    let message = context.to_string();
+                    let mut next = match error {
+                        Some(error) => error.stack_err_msg(message),
+                        None => StackError::from_msg(message),
+                    };
+                    if let Some(stack_error) = context.downcast_ref::<StackError>() {
+                        if let Some(code) = stack_error.err_code() {
+                            next = next.with_err_code(*code);
+                        }
+                        if let Some(uri) = stack_error.err_uri() {
+                            next = next.with_err_uri(uri.to_string());
+                        }
+                    }
+                    error = Some(next);
+                }
+                FrameKind::Attachment(AttachmentKind::Opaque(_)) => {
+                    if let Some((key, value)) = frame.downcast_ref::<(String, String)>() {
+                        error = error.map(|e| e.with_err_field(key.clone(), value.clone()));
+                    }
+                }
+                FrameKind::Attachment(AttachmentKind::Printable(attachment)) => {
+                    error = error.map(|e| e.with_err_field("attachment", attachment));
+                }
+                FrameKind::Attachment(_) => {}
+            }
+        }
+        error.unwrap_or_default()
+    }
+}
+
+impl StackError {
+    /// Converts the error into an `error_stack::Report`, with one context per
+    /// frame. Fields are added as opaque `(String, String)` attachments, so
+    /// that their keys are kept when converting the report back.
+    pub fn into_report(self) -> Report<StackError> {
+        let mut report: Option<Report<StackError>> = None;
+        for frame in self.into_frames() {
+            let fields = frame.err_fields().to_vec();
+            let frame = frame.with_no_err_fields();
+            let mut next = match report {
+                Some(report) => report.change_context(frame),
+                None => Report::new(frame),
+            };
+            for (key, value) in fields {
+                next = next.attach_opaque((key, value));
+            }
+            report = Some(next);
+        }
+        report.expect("an error has at least one frame")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_report_into_stackerror() {
+        let report = Report::new(std::io::Error::other("Base error"))
+            .attach("path: config.toml")
+            .change_context(
+                StackError::from_msg("Stacked error").with_err_code(ErrorCode::IoOther),
+            );
+        let error: StackError = report.into();
+        assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoOther));
+    }
+
+    #[test]
+    fn test_stackerror_round_trips() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .stack_err_msg("Stacked error");
+        let report = error.into_report();
+        assert_eq!(
+            report.current_context().err_code(),
+            Some(&ErrorCode::IoNotFound)
+        );
+        let error: StackError = report.into();
+        assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+        let source = std::error::Error::source(&error)
+            .and_then(|e| e.downcast_ref::<StackError>())
+            .unwrap();
+        assert_eq!(
+            source.err_fields(),
+            &[("path".to_string(), "config.toml".to_string())]
+        );
+    }
+}
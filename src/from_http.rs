@@ -1,11 +1,13 @@
-//! Conversions from `http` types into `StackError`.
+//! Conversions from `http` types into `StackError`. The conversions between
+//! status codes and `ErrorCode` are in the `codes` module, as they don't
+//! require `std`.
 
-use crate::codes::ErrorCode;
 use crate::error::{ErrorStacks, StackError};
+use crate::mapper::code_mapper;
 
 impl From<http::StatusCode> for StackError {
     fn from(status: http::StatusCode) -> Self {
-        let code = ErrorCode::from_http_value(status.as_u16());
+        let code = code_mapper().map_http_status(status.as_u16());
         let err = StackError::from_msg(status);
         match code {
             Some(mapped) => err.with_err_code(mapped),
@@ -13,3 +15,16 @@ impl From<http::StatusCode> for StackError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_status_code_converts() {
+        let error = StackError::from(http::StatusCode::CONFLICT);
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpConflict));
+        assert_eq!(error.to_string(), "409 Conflict");
+    }
+}
@@ -0,0 +1,111 @@
+//! Conversions from `rdkafka` errors into `StackError`.
+
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::hint::RecoveryHint;
+
+/// Map a librdkafka error code to an error code and a recovery hint.
+fn classify(code: RDKafkaErrorCode) -> (Option<ErrorCode>, Option<RecoveryHint>) {
+    use RDKafkaErrorCode::*;
+
+    match code {
+        BrokerTransportFailure => (
+            Some(ErrorCode::IoConnectionReset),
+            Some(RecoveryHint::Retry),
+        ),
+        AllBrokersDown => (Some(ErrorCode::IoNotConnected), Some(RecoveryHint::Retry)),
+        MessageTimedOut | RequestTimedOut => {
+            (Some(ErrorCode::IoTimedOut), Some(RecoveryHint::Retry))
+        }
+        PartitionEOF => (Some(ErrorCode::IoUnexpectedEof), Some(RecoveryHint::Retry)),
+        QueueFull => (Some(ErrorCode::IoWouldBlock), Some(RecoveryHint::Retry)),
+        LeaderNotAvailable
+        | NotLeaderForPartition
+        | PreferredLeaderNotAvailable
+        | NotEnoughReplicas => (
+            Some(ErrorCode::HttpServiceUnavailable),
+            Some(RecoveryHint::Retry),
+        ),
+        Authentication | SaslAuthenticationFailed => (
+            Some(ErrorCode::HttpUnauthorized),
+            Some(RecoveryHint::Reauthenticate),
+        ),
+        TopicAuthorizationFailed => (Some(ErrorCode::HttpForbidden), Some(RecoveryHint::GiveUp)),
+        UnknownTopicOrPartition => (Some(ErrorCode::IoNotFound), Some(RecoveryHint::GiveUp)),
+        MessageSizeTooLarge => (
+            Some(ErrorCode::HttpPayloadTooLarge),
+            Some(RecoveryHint::GiveUp),
+        ),
+        InvalidConfig => (Some(ErrorCode::IoInvalidInput), Some(RecoveryHint::GiveUp)),
+        _ => (None, None),
+    }
+}
+
+/// Transient failures, such as broker transport failures and timeouts, have
+/// the [`RecoveryHint::Retry`] hint, and permanent failures, such as unknown
+/// topics and invalid configurations, have the [`RecoveryHint::GiveUp`]
+/// hint. The librdkafka error code is set as the `rdkafka_code` field.
+impl From<KafkaError> for StackError {
+    fn from(error: KafkaError) -> Self {
+        let (code, mut hint) = match &error {
+            KafkaError::PartitionEOF(_) => {
+                (Some(ErrorCode::IoUnexpectedEof), Some(RecoveryHint::Retry))
+            }
+            KafkaError::ClientConfig(..) | KafkaError::ClientCreation(_) => {
+                (Some(ErrorCode::IoInvalidInput), Some(RecoveryHint::GiveUp))
+            }
+            error => error.rdkafka_error_code().map_or((None, None), classify),
+        };
+        match &error {
+            KafkaError::MessageConsumptionFatal(_) => hint = Some(RecoveryHint::GiveUp),
+            KafkaError::Transaction(transaction) if transaction.is_fatal() => {
+                hint = Some(RecoveryHint::GiveUp)
+            }
+            KafkaError::Transaction(transaction) if transaction.is_retriable() => {
+                hint = Some(RecoveryHint::Retry)
+            }
+            _ => {}
+        }
+        let rdkafka_code = error.rdkafka_error_code();
+        let mut stacked = StackError::from_err(error);
+        if let Some(code) = code {
+            stacked = stacked.with_err_code(code);
+        }
+        if let Some(hint) = hint {
+            stacked = stacked.with_err_hint(hint);
+        }
+        if let Some(rdkafka_code) = rdkafka_code {
+            stacked = stacked.with_err_field("rdkafka_code", format!("{rdkafka_code:?}"));
+        }
+        stacked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kafka_error_classification() {
+        let error = StackError::from(KafkaError::MessageProduction(
+            RDKafkaErrorCode::MessageTimedOut,
+        ));
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoTimedOut));
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::Retry));
+        assert_eq!(
+            error.err_fields(),
+            &[("rdkafka_code".to_string(), "MessageTimedOut".to_string())]
+        );
+
+        let error = StackError::from(KafkaError::MessageConsumptionFatal(
+            RDKafkaErrorCode::BrokerTransportFailure,
+        ));
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoConnectionReset));
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::GiveUp));
+
+        let error = StackError::from(KafkaError::PartitionEOF(3));
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoUnexpectedEof));
+    }
+}
@@ -0,0 +1,97 @@
+//! Conversions from `openssl` errors into `StackError`.
+
+use openssl::error::{Error, ErrorStack};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::hint::RecoveryHint;
+
+// Library and reason codes from `openssl/err.h` and `openssl/sslerr.h`.
+const ERR_LIB_PEM: i32 = 9;
+const ERR_LIB_X509: i32 = 11;
+const ERR_LIB_ASN1: i32 = 13;
+const ERR_LIB_SSL: i32 = 20;
+const ERR_LIB_X509V3: i32 = 34;
+const SSL_R_CERTIFICATE_VERIFY_FAILED: i32 = 134;
+
+/// Map an OpenSSL error to an error code and a recovery hint.
+fn classify(error: &Error) -> (ErrorCode, Option<RecoveryHint>) {
+    match (error.library_code(), error.reason_code()) {
+        (ERR_LIB_SSL, SSL_R_CERTIFICATE_VERIFY_FAILED) => {
+            (ErrorCode::IoPermissionDenied, Some(RecoveryHint::GiveUp))
+        }
+        (ERR_LIB_SSL, _) => (ErrorCode::IoConnectionAborted, None),
+        (ERR_LIB_PEM | ERR_LIB_X509 | ERR_LIB_ASN1 | ERR_LIB_X509V3, _) => {
+            (ErrorCode::IoInvalidData, Some(RecoveryHint::GiveUp))
+        }
+        _ => (ErrorCode::IoOther, None),
+    }
+}
+
+/// Each error in the OpenSSL error queue becomes a frame, with the first
+/// queued error as the root. Each frame has the `openssl_library` and
+/// `openssl_reason` fields, and a code for its library: handshake failures
+/// are [`ErrorCode::IoConnectionAborted`], certificate verification
+/// failures are [`ErrorCode::IoPermissionDenied`], and certificate and key
+/// parsing failures are [`ErrorCode::IoInvalidData`].
+impl From<ErrorStack> for StackError {
+    fn from(error: ErrorStack) -> Self {
+        let mut stacked: Option<StackError> = None;
+        for error in error.errors() {
+            let (code, hint) = classify(error);
+            let frame = match stacked {
+                Some(stacked) => stacked.stack_err_msg(error.clone()),
+                None => StackError::from_err(error.clone()),
+            };
+            let frame = frame
+                .with_err_code(code)
+                .with_err_field("openssl_library", error.library_code())
+                .with_err_field("openssl_reason", error.reason_code());
+            stacked = Some(match hint {
+                Some(hint) => frame.with_err_hint(hint),
+                None => frame.with_no_err_hint(),
+            });
+        }
+        stacked.unwrap_or_else(|| StackError::from_err(error).with_err_code(ErrorCode::IoOther))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openssl_error_stack() {
+        let error = openssl::x509::X509::from_pem(b"not a certificate").unwrap_err();
+        let count = error.errors().len();
+        assert!(count > 0);
+        let first = error.errors()[0].clone();
+
+        let error = StackError::from(error);
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidData));
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::GiveUp));
+        assert_eq!(
+            error.err_fields()[0],
+            ("openssl_library".to_string(), ERR_LIB_PEM.to_string())
+        );
+
+        let root = std::iter::successors(Some(&error), |e| {
+            std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+        })
+        .last()
+        .unwrap();
+        assert_eq!(
+            root.err_fields()[1],
+            (
+                "openssl_reason".to_string(),
+                first.reason_code().to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_openssl_empty_error_stack() {
+        let error = StackError::from(ErrorStack::get());
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoOther));
+    }
+}
@@ -1,5 +1,6 @@
 //! Conversions from `reqwest` types into `StackError`.
 
+use crate::codes::ErrorCode;
 use crate::error::{ErrorStacks, StackError};
 
 impl From<reqwest::Error> for StackError {
@@ -10,6 +11,18 @@ impl From<reqwest::Error> for StackError {
             let base: StackError = status.into();
             return base.stack_err_msg(error);
         }
+        if error.is_timeout() {
+            return StackError::from_msg(error).with_err_code(ErrorCode::IoTimedOut);
+        }
         StackError::from_msg(error)
     }
 }
+
+/// Builds an error from the status and headers of a response, as by
+/// [`from_error_headers`](crate::headers::from_error_headers), e.g. to keep
+/// the `Retry-After` delay of a rate-limited request.
+impl From<&reqwest::Response> for StackError {
+    fn from(response: &reqwest::Response) -> Self {
+        crate::headers::from_error_headers(response.status(), response.headers())
+    }
+}
@@ -0,0 +1,53 @@
+//! Conversions from `serde_json` types into `StackError`.
+
+use serde_json::error::Category;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::mapper::code_mapper;
+
+/// Syntax and data errors have the `IoInvalidData` code, and the position of
+/// the error is attached as `line` and `column` fields.
+impl From<serde_json::Error> for StackError {
+    fn from(error: serde_json::Error) -> Self {
+        let code = match error.classify() {
+            Category::Io => error
+                .io_error_kind()
+                .and_then(|kind| code_mapper().map_io_kind(kind)),
+            Category::Syntax | Category::Data => Some(ErrorCode::IoInvalidData),
+            Category::Eof => Some(ErrorCode::IoUnexpectedEof),
+        };
+        let (line, column) = (error.line(), error.column());
+        let mut err = StackError::from_err(error);
+        if line > 0 {
+            err = err
+                .with_err_field("line", line)
+                .with_err_field("column", column);
+        }
+        match code {
+            Some(code) => err.with_err_code(code),
+            None => err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_serde_json_error() {
+        let error: StackError = serde_json::from_str::<u32>("\n  true").unwrap_err().into();
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidData));
+        assert_eq!(
+            error.err_fields(),
+            &[
+                ("line".to_string(), "2".to_string()),
+                ("column".to_string(), "6".to_string())
+            ]
+        );
+        assert!(error.downcast_ref::<serde_json::Error>().is_some());
+        let error: StackError = serde_json::from_str::<u32>("").unwrap_err().into();
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoUnexpectedEof));
+    }
+}
@@ -1,16 +1,30 @@
-//! Conversions from `std::io` types into `StackError`.
+//! Conversions between `std::io` types and `StackError`.
 
-use crate::codes::ErrorCode;
 use crate::error::{ErrorStacks, StackError};
+use crate::mapper::code_mapper;
 
+/// The `io::Error` is kept as the wrapped error, so that it can be retrieved
+/// with [`StackError::downcast_ref`], e.g. for its `raw_os_error()`.
 impl From<std::io::Error> for StackError {
     fn from(error: std::io::Error) -> Self {
         // Capture the kind for mapping before moving the error into the message box
         let kind = error.kind();
-        let err = StackError::from_msg(error);
-        match ErrorCode::from_io_kind(kind) {
+        let err = StackError::from_err(error);
+        match code_mapper().map_io_kind(kind) {
             Some(code) => err.with_err_code(code),
             None => err,
         }
     }
 }
+
+/// The kind is mapped from the error code, defaulting to `Other`, and the
+/// `StackError` is kept as the inner error.
+impl From<StackError> for std::io::Error {
+    fn from(error: StackError) -> Self {
+        let kind = error
+            .err_code()
+            .and_then(|code| code.to_io_kind())
+            .unwrap_or(std::io::ErrorKind::Other);
+        std::io::Error::new(kind, error)
+    }
+}
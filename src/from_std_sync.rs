@@ -0,0 +1,82 @@
+//! Conversions from `std::sync::mpsc` errors into `StackError`.
+//!
+//! A disconnected channel maps to `IoBrokenPipe`, a full or empty channel to
+//! `IoWouldBlock`, and a receive timeout to `IoTimedOut`. Values which
+//! couldn't be sent are dropped, as the error must be `Send + Sync`.
+
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+impl<T> From<SendError<T>> for StackError {
+    fn from(error: SendError<T>) -> Self {
+        StackError::from_msg(error.to_string()).with_err_code(ErrorCode::IoBrokenPipe)
+    }
+}
+
+impl<T> From<TrySendError<T>> for StackError {
+    fn from(error: TrySendError<T>) -> Self {
+        let code = match error {
+            TrySendError::Full(_) => ErrorCode::IoWouldBlock,
+            TrySendError::Disconnected(_) => ErrorCode::IoBrokenPipe,
+        };
+        StackError::from_msg(error.to_string()).with_err_code(code)
+    }
+}
+
+impl From<RecvError> for StackError {
+    fn from(error: RecvError) -> Self {
+        StackError::from_err(error).with_err_code(ErrorCode::IoBrokenPipe)
+    }
+}
+
+impl From<TryRecvError> for StackError {
+    fn from(error: TryRecvError) -> Self {
+        let code = match error {
+            TryRecvError::Empty => ErrorCode::IoWouldBlock,
+            TryRecvError::Disconnected => ErrorCode::IoBrokenPipe,
+        };
+        StackError::from_err(error).with_err_code(code)
+    }
+}
+
+impl From<RecvTimeoutError> for StackError {
+    fn from(error: RecvTimeoutError) -> Self {
+        let code = match error {
+            RecvTimeoutError::Timeout => ErrorCode::IoTimedOut,
+            RecvTimeoutError::Disconnected => ErrorCode::IoBrokenPipe,
+        };
+        StackError::from_err(error).with_err_code(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_channel_errors_have_codes() {
+        let (sender, receiver) = mpsc::sync_channel::<u32>(1);
+        assert_eq!(
+            StackError::from(receiver.try_recv().unwrap_err()).err_code(),
+            Some(&ErrorCode::IoWouldBlock)
+        );
+        sender.send(1).unwrap();
+        assert_eq!(
+            StackError::from(sender.try_send(2).unwrap_err()).err_code(),
+            Some(&ErrorCode::IoWouldBlock)
+        );
+        drop(receiver);
+        let error = StackError::from(sender.send(3).unwrap_err());
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoBrokenPipe));
+        assert_eq!(error.to_string(), "sending on a closed channel");
+
+        let (sender, receiver) = mpsc::channel::<u32>();
+        drop(sender);
+        let error = StackError::from(receiver.recv().unwrap_err());
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoBrokenPipe));
+        assert!(error.downcast_ref::<RecvError>().is_some());
+    }
+}
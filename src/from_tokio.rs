@@ -0,0 +1,94 @@
+//! Conversions from `tokio::sync` errors into `StackError`.
+//!
+//! As for `std::sync::mpsc`, a closed channel or semaphore maps to
+//! `IoBrokenPipe` and a full channel to `IoWouldBlock`. Values which couldn't
+//! be sent are dropped.
+
+use tokio::sync::{broadcast, mpsc, oneshot, AcquireError};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+impl From<oneshot::error::RecvError> for StackError {
+    fn from(error: oneshot::error::RecvError) -> Self {
+        StackError::from_err(error).with_err_code(ErrorCode::IoBrokenPipe)
+    }
+}
+
+/// A lagging receiver maps to `IoInterrupted`, as it can keep receiving, and
+/// the number of skipped messages is set as the `skipped` field.
+impl From<broadcast::error::RecvError> for StackError {
+    fn from(error: broadcast::error::RecvError) -> Self {
+        match error {
+            broadcast::error::RecvError::Closed => {
+                StackError::from_err(error).with_err_code(ErrorCode::IoBrokenPipe)
+            }
+            broadcast::error::RecvError::Lagged(skipped) => StackError::from_err(error)
+                .with_err_code(ErrorCode::IoInterrupted)
+                .with_err_field("skipped", skipped),
+        }
+    }
+}
+
+impl From<AcquireError> for StackError {
+    fn from(error: AcquireError) -> Self {
+        StackError::from_err(error).with_err_code(ErrorCode::IoBrokenPipe)
+    }
+}
+
+impl<T> From<mpsc::error::SendError<T>> for StackError {
+    fn from(error: mpsc::error::SendError<T>) -> Self {
+        StackError::from_msg(error.to_string()).with_err_code(ErrorCode::IoBrokenPipe)
+    }
+}
+
+impl<T> From<mpsc::error::TrySendError<T>> for StackError {
+    fn from(error: mpsc::error::TrySendError<T>) -> Self {
+        let code = match error {
+            mpsc::error::TrySendError::Full(_) => ErrorCode::IoWouldBlock,
+            mpsc::error::TrySendError::Closed(_) => ErrorCode::IoBrokenPipe,
+        };
+        StackError::from_msg(error.to_string()).with_err_code(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_errors_have_codes() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let (sender, receiver) = oneshot::channel::<u32>();
+            drop(sender);
+            let error = StackError::from(receiver.await.unwrap_err());
+            assert_eq!(error.err_code(), Some(&ErrorCode::IoBrokenPipe));
+
+            let (sender, mut receiver) = broadcast::channel::<u32>(1);
+            sender.send(1).unwrap();
+            sender.send(2).unwrap();
+            let error = StackError::from(receiver.recv().await.unwrap_err());
+            assert_eq!(error.err_code(), Some(&ErrorCode::IoInterrupted));
+            assert_eq!(
+                error.err_fields(),
+                &[("skipped".to_string(), "1".to_string())]
+            );
+
+            let semaphore = tokio::sync::Semaphore::new(1);
+            semaphore.close();
+            let error = StackError::from(semaphore.acquire().await.unwrap_err());
+            assert_eq!(error.err_code(), Some(&ErrorCode::IoBrokenPipe));
+
+            let (sender, receiver) = mpsc::channel::<u32>(1);
+            sender.send(1).await.unwrap();
+            let error = StackError::from(sender.try_send(2).unwrap_err());
+            assert_eq!(error.err_code(), Some(&ErrorCode::IoWouldBlock));
+            drop(receiver);
+            let error = StackError::from(sender.send(3).await.unwrap_err());
+            assert_eq!(error.err_code(), Some(&ErrorCode::IoBrokenPipe));
+        });
+    }
+}
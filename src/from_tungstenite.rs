@@ -0,0 +1,78 @@
+//! Conversions from `tungstenite` errors into `StackError`.
+
+use tungstenite::error::{CapacityError, Error, ProtocolError};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::mapper::code_mapper;
+
+/// Closed connections have connection codes, so that they can be told apart
+/// from protocol violations, which have the `IoInvalidData` code. I/O errors
+/// and handshake responses are mapped as `io::Error` and `StatusCode` are.
+impl From<Error> for StackError {
+    fn from(error: Error) -> Self {
+        let code = match &error {
+            Error::ConnectionClosed => Some(ErrorCode::IoConnectionAborted),
+            Error::AlreadyClosed => Some(ErrorCode::IoNotConnected),
+            Error::Io(io) => code_mapper().map_io_kind(io.kind()),
+            Error::Capacity(CapacityError::TooManyHeaders) => {
+                Some(ErrorCode::HttpRequestHeaderFieldsTooLarge)
+            }
+            Error::Capacity(CapacityError::MessageTooLong { .. }) => {
+                Some(ErrorCode::HttpPayloadTooLarge)
+            }
+            Error::Protocol(ProtocolError::ResetWithoutClosingHandshake) => {
+                Some(ErrorCode::IoConnectionReset)
+            }
+            Error::Protocol(_) | Error::Utf8(_) | Error::AttackAttempt | Error::HttpFormat(_) => {
+                Some(ErrorCode::IoInvalidData)
+            }
+            Error::WriteBufferFull(_) => Some(ErrorCode::IoWouldBlock),
+            Error::Url(_) => Some(ErrorCode::IoInvalidInput),
+            Error::Http(response) => code_mapper().map_http_status(response.status().as_u16()),
+            _ => None,
+        };
+        let stacked = StackError::from_err(error);
+        match code {
+            Some(code) => stacked.with_err_code(code),
+            None => stacked,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tungstenite_error_codes() {
+        let code = |error: Error| StackError::from(error).err_code().copied();
+        assert_eq!(
+            code(Error::ConnectionClosed),
+            Some(ErrorCode::IoConnectionAborted)
+        );
+        assert_eq!(
+            code(Error::Protocol(ProtocolError::ResetWithoutClosingHandshake)),
+            Some(ErrorCode::IoConnectionReset)
+        );
+        assert_eq!(
+            code(Error::Protocol(ProtocolError::NonZeroReservedBits)),
+            Some(ErrorCode::IoInvalidData)
+        );
+        assert_eq!(
+            code(Error::Capacity(CapacityError::MessageTooLong {
+                size: 2,
+                max_size: 1
+            })),
+            Some(ErrorCode::HttpPayloadTooLarge)
+        );
+        let response = tungstenite::http::Response::builder()
+            .status(503)
+            .body(None)
+            .unwrap();
+        assert_eq!(
+            code(Error::Http(Box::new(response))),
+            Some(ErrorCode::HttpServiceUnavailable)
+        );
+    }
+}
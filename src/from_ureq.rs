@@ -0,0 +1,89 @@
+//! Conversions from `ureq` errors into `StackError`.
+
+use std::time::Duration;
+
+use ureq::ErrorKind;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::mapper::code_mapper;
+
+/// Status errors have the code mapped from their status, and the
+/// `Retry-After` delay of 429 and 503 responses. Transport errors have a
+/// code for their kind, mapped from the IO error kind for IO failures such as
+/// timeouts. The URL, after redirects, is set as the error URI.
+impl From<ureq::Error> for StackError {
+    fn from(error: ureq::Error) -> Self {
+        let (code, url, retry_after) = match &error {
+            ureq::Error::Status(status, response) => {
+                let retry_after = match status {
+                    429 | 503 => response
+                        .header("retry-after")
+                        .and_then(|v| v.trim().parse().ok())
+                        .map(Duration::from_secs),
+                    _ => None,
+                };
+                (
+                    code_mapper().map_http_status(*status),
+                    Some(response.get_url().to_string()),
+                    retry_after,
+                )
+            }
+            ureq::Error::Transport(transport) => {
+                let code = match transport.kind() {
+                    ErrorKind::InvalidUrl
+                    | ErrorKind::UnknownScheme
+                    | ErrorKind::InsecureRequestHttpsOnly
+                    | ErrorKind::InvalidProxyUrl => Some(ErrorCode::IoInvalidInput),
+                    ErrorKind::Dns => Some(ErrorCode::IoNotFound),
+                    ErrorKind::ConnectionFailed | ErrorKind::ProxyConnect => {
+                        Some(ErrorCode::IoConnectionRefused)
+                    }
+                    ErrorKind::BadStatus | ErrorKind::BadHeader => Some(ErrorCode::IoInvalidData),
+                    ErrorKind::ProxyUnauthorized => {
+                        Some(ErrorCode::HttpProxyAuthenticationRequired)
+                    }
+                    ErrorKind::Io => std::error::Error::source(transport)
+                        .and_then(|e| e.downcast_ref::<std::io::Error>())
+                        .and_then(|e| code_mapper().map_io_kind(e.kind())),
+                    _ => None,
+                };
+                (code, transport.url().map(|url| url.to_string()), None)
+            }
+        };
+        let mut stacked = StackError::from_err(error);
+        if let Some(code) = code {
+            stacked = stacked.with_err_code(code);
+        }
+        if let Some(url) = url {
+            stacked = stacked.with_err_uri(url);
+        }
+        if let Some(delay) = retry_after {
+            stacked = stacked.with_retry_after(delay);
+        }
+        stacked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ureq_status_error() {
+        let response = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 30\r\n\r\n"
+            .parse::<ureq::Response>()
+            .unwrap();
+        let url = response.get_url().to_string();
+        let error = StackError::from(ureq::Error::Status(429, response));
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpTooManyRequests));
+        assert_eq!(error.err_uri(), Some(url.as_str()));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_ureq_transport_error() {
+        let error = StackError::from(ureq::get("not a url").call().unwrap_err());
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidInput));
+    }
+}
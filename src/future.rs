@@ -0,0 +1,75 @@
+//! Provides the [`StackFutureExt`] trait for annotating errors on futures.
+
+use std::future::Future;
+
+use futures_util::future::{TryFuture, TryFutureExt};
+
+use crate::error::ErrorStacks;
+
+/// Extension trait for futures resolving to a [`Result`] whose error
+/// implements [`ErrorStacks`].
+///
+/// The methods mirror those of [`ErrorStacks`] on [`Result`], so an async
+/// call chain can be annotated without an intermediate `.await`.
+pub trait StackFutureExt<C>: TryFuture + Sized
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    Self::Error: ErrorStacks<C>,
+{
+    /// Stack a new error with the given message if the future fails. The
+    /// frame has the location of the caller, rather than where it is polled.
+    #[track_caller]
+    fn stack_err_msg(
+        self,
+        error: impl std::fmt::Display + Send + Sync + 'static,
+    ) -> impl Future<Output = Result<Self::Ok, Self::Error>> {
+        let location = std::panic::Location::caller();
+        self.into_future()
+            .map_err(move |e| e.stack_err_msg(error).with_err_location(location))
+    }
+
+    /// Set the error code if the future fails.
+    fn with_err_code(self, code: C) -> impl Future<Output = Result<Self::Ok, Self::Error>> {
+        self.into_future().map_err(|e| e.with_err_code(code))
+    }
+}
+
+impl<F, C> StackFutureExt<C> for F
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    F: TryFuture,
+    F::Error: ErrorStacks<C>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use futures_util::FutureExt;
+
+    #[test]
+    fn test_future_stacks_err_msg() {
+        let fut = std::future::ready(StackResult::<()>::Err(StackError::from_msg("Base error")));
+        let line = line!() + 2;
+        let result = fut
+            .stack_err_msg("Stacked error")
+            .with_err_code(ErrorCode::IoTimedOut)
+            .now_or_never()
+            .unwrap();
+        assert_eq!(result.err_code(), Some(&ErrorCode::IoTimedOut));
+        let location = result.as_ref().unwrap_err().err_location().unwrap();
+        assert_eq!((location.file(), location.line()), ("src/future.rs", line));
+        assert_eq!(
+            format!("{:?}", result.unwrap_err()),
+            "Base error\nStacked error"
+        );
+    }
+
+    #[test]
+    fn test_future_passes_ok() {
+        let fut = std::future::ready(StackResult::<_>::Ok(42));
+        let result = fut.stack_err_msg("Stacked error").now_or_never().unwrap();
+        assert_eq!(result.unwrap(), 42);
+    }
+}
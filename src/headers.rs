@@ -0,0 +1,135 @@
+//! Provides helpers for propagating errors through HTTP headers, for
+//! services which don't want to parse response bodies on every failure.
+
+use std::time::Duration;
+
+use http::header::RETRY_AFTER;
+use http::{HeaderMap, HeaderValue, StatusCode};
+
+use crate::error::{ErrorStacks, StackError};
+
+/// Header carrying the error code name.
+pub const ERROR_CODE_HEADER: &str = "x-error-code";
+/// Header carrying the error fingerprint, as 16 hexadecimal digits.
+pub const ERROR_FINGERPRINT_HEADER: &str = "x-error-fingerprint";
+/// Header carrying the error URI.
+pub const ERROR_URI_HEADER: &str = "x-error-uri";
+
+/// Insert the code, fingerprint and URI of an error into response headers,
+/// and the retry delay as the `Retry-After` header.
+///
+/// Attributes which aren't set, or which aren't valid header values, are
+/// skipped.
+pub fn insert_error_headers(error: &StackError, headers: &mut HeaderMap) {
+    if let Some(code) = error.err_code() {
+        if let Ok(value) = HeaderValue::from_str(&format!("{code:?}")) {
+            headers.insert(ERROR_CODE_HEADER, value);
+        }
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("{:016x}", error.err_fingerprint())) {
+        headers.insert(ERROR_FINGERPRINT_HEADER, value);
+    }
+    if let Some(uri) = error.err_uri() {
+        if let Ok(value) = HeaderValue::from_str(uri) {
+            headers.insert(ERROR_URI_HEADER, value);
+        }
+    }
+    if let Some(delay) = error.retry_after() {
+        headers.insert(RETRY_AFTER, HeaderValue::from(delay.as_secs()));
+    }
+}
+
+/// Build an error from a response status and the headers set by
+/// [`insert_error_headers`].
+///
+/// The error is built from the status as by `From<StatusCode>`. Its code is
+/// replaced by the header code if it is known, and the fingerprint is added
+/// as the `fingerprint` field. For `429 Too Many Requests` and
+/// `503 Service Unavailable` responses, a `Retry-After` header given in
+/// seconds sets the retry delay. HTTP dates are ignored.
+pub fn from_error_headers(status: StatusCode, headers: &HeaderMap) -> StackError {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let mut error = StackError::from(status);
+    if let Some(code) = header(ERROR_CODE_HEADER).and_then(|v| v.parse().ok()) {
+        error = error.with_err_code(code);
+    }
+    if let Some(uri) = header(ERROR_URI_HEADER) {
+        error = error.with_err_uri(uri.to_string());
+    }
+    if let Some(fingerprint) = header(ERROR_FINGERPRINT_HEADER) {
+        error = error.with_err_field("fingerprint", fingerprint);
+    }
+    if matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        if let Some(secs) = header(RETRY_AFTER.as_str()).and_then(|v| v.trim().parse().ok()) {
+            error = error.with_retry_after(Duration::from_secs(secs));
+        }
+    }
+    error
+}
+
+/// Builds an error from the status and headers of a response, as by
+/// [`from_error_headers`].
+impl<B> From<&http::Response<B>> for StackError {
+    fn from(response: &http::Response<B>) -> Self {
+        from_error_headers(response.status(), response.headers())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_headers_round_trip() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_uri("https://example.com/not-found".to_string());
+        let mut headers = HeaderMap::new();
+        insert_error_headers(&error, &mut headers);
+        assert_eq!(headers[ERROR_CODE_HEADER], "IoNotFound");
+
+        let decoded = from_error_headers(StatusCode::NOT_FOUND, &headers);
+        assert_eq!(format!("{:?}", decoded), "404 Not Found");
+        assert_eq!(decoded.err_code(), Some(&ErrorCode::IoNotFound));
+        assert_eq!(decoded.err_uri(), Some("https://example.com/not-found"));
+        assert_eq!(
+            decoded.err_fields(),
+            &[(
+                "fingerprint".to_string(),
+                format!("{:016x}", error.err_fingerprint())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_headers_retry_after() {
+        let error = StackError::from_msg("Base error").with_retry_after(Duration::from_secs(30));
+        let mut headers = HeaderMap::new();
+        insert_error_headers(&error, &mut headers);
+        assert_eq!(headers[RETRY_AFTER], "30");
+
+        let decoded = from_error_headers(StatusCode::TOO_MANY_REQUESTS, &headers);
+        assert_eq!(decoded.retry_after(), Some(Duration::from_secs(30)));
+        let decoded = from_error_headers(StatusCode::NOT_FOUND, &headers);
+        assert_eq!(decoded.retry_after(), None);
+
+        let response = http::Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header(RETRY_AFTER, "120")
+            .body(())
+            .unwrap();
+        let decoded = StackError::from(&response);
+        assert_eq!(decoded.retry_after(), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_headers_missing() {
+        let decoded = from_error_headers(StatusCode::BAD_GATEWAY, &HeaderMap::new());
+        assert_eq!(decoded.err_code(), Some(&ErrorCode::HttpBadGateway));
+        assert!(decoded.err_fields().is_empty());
+    }
+}
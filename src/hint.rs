@@ -0,0 +1,27 @@
+//! Provides the [`RecoveryHint`] type, telling callers how they could
+//! recover from an error.
+
+use core::time::Duration;
+
+/// A machine-readable hint of how a caller could recover from an error.
+///
+/// Hints are set with
+/// [`ErrorStacks::with_err_hint`](crate::error::ErrorStacks::with_err_hint)
+/// and are inherited by stacked frames, as are codes. They complement the
+/// code: a `HttpServiceUnavailable` error can be worth retrying after a
+/// delay, or not at all if the service is being decommissioned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum RecoveryHint {
+    /// The operation can be retried immediately.
+    Retry,
+    /// The operation can be retried after the given delay.
+    RetryAfter(Duration),
+    /// The credentials should be refreshed before retrying.
+    Reauthenticate,
+    /// The operation can be retried with a fallback resource, e.g. a replica.
+    UseFallbackResource,
+    /// The operation shouldn't be retried.
+    GiveUp,
+}
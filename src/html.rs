@@ -0,0 +1,234 @@
+//! Provides the rendering of errors as HTML error pages, for web servers.
+//!
+//! In debug builds, pages show the chain of the error with the code, URI and
+//! fields of each frame, and the backtrace of the error, as web frameworks do
+//! in development. In release builds, pages only show the HTTP status, so
+//! that internals aren't leaked to clients. Messages, URIs and fields are
+//! passed through the [redactor](crate::redact) either way.
+//!
+//! With the `http` feature, [`HtmlRenderer::response`] builds a response
+//! which web frameworks built on `http`, such as axum, can return directly:
+//!
+//! ```rust
+//! async fn handler() -> Result<String, http::Response<String>> {
+//!     load_page().await.map_err(|error| HtmlRenderer::new().response(&error))
+//! }
+//! ```
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::redact::redact;
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem auto;max-width:60rem;padding:0 1rem;color:#1f2328}\
+h1{color:#b42318;font-size:1.5rem}\
+ol{padding-left:1.5rem}\
+li{margin-bottom:1rem}\
+.message{font-family:ui-monospace,monospace;font-weight:600}\
+.code{background:#fee4e2;border-radius:4px;color:#b42318;font-size:.85rem;margin-left:.5rem;padding:0 .4rem}\
+table{border-collapse:collapse;font-size:.9rem;margin-top:.4rem}\
+td{border-top:1px solid #d0d7de;padding:.2rem .8rem .2rem 0;vertical-align:top}\
+td:first-child{color:#59636e}\
+pre{background:#f6f8fa;font-size:.8rem;overflow-x:auto;padding:1rem}";
+
+/// Renders errors as HTML error pages.
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlRenderer {
+    details: bool,
+    backtrace: bool,
+}
+
+impl Default for HtmlRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HtmlRenderer {
+    /// Creates a renderer which shows the details of errors and their
+    /// backtraces in debug builds only.
+    pub fn new() -> Self {
+        Self {
+            details: cfg!(debug_assertions),
+            backtrace: cfg!(debug_assertions),
+        }
+    }
+
+    /// Set whether the chain, codes, URIs and fields of errors are shown.
+    pub fn with_details(self, details: bool) -> Self {
+        Self { details, ..self }
+    }
+
+    /// Set whether backtraces are shown, if details are shown.
+    pub fn with_backtrace(self, backtrace: bool) -> Self {
+        Self { backtrace, ..self }
+    }
+
+    /// Render the error as an HTML page.
+    pub fn render(&self, error: &StackError) -> String {
+        let status = http_status(error);
+        let title = match self.details {
+            true => escape(&redact(&error.frame_msg().to_string())),
+            false => format!("Error {status}"),
+        };
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+        if self.details {
+            out += &self.render_details(error);
+        }
+        out += "</body>\n</html>\n";
+        out
+    }
+
+    /// Render the frames from the root to the top, and the backtrace.
+    fn render_details(&self, error: &StackError) -> String {
+        let mut frames: Vec<_> = std::iter::successors(Some(error), |e| {
+            std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+        })
+        .collect();
+        frames.reverse();
+        let mut out = String::from("<ol start=\"0\">\n");
+        for frame in frames {
+            out += "<li>";
+            out += &format!(
+                "<span class=\"message\">{}</span>",
+                escape(&redact(&frame.frame_msg().to_string()))
+            );
+            if let Some(code) = frame.err_code() {
+                out += &format!("<span class=\"code\">{code:?}</span>");
+            }
+            let mut rows = Vec::new();
+            if let Some(uri) = frame.err_uri() {
+                let uri = escape(&redact(uri));
+                let value = match uri.starts_with("http://") || uri.starts_with("https://") {
+                    true => format!("<a href=\"{uri}\">{uri}</a>"),
+                    false => uri,
+                };
+                rows.push(("uri".to_string(), value));
+            }
+            for (key, value) in frame.err_fields() {
+                rows.push((escape(key), escape(&redact(value))));
+            }
+            if !rows.is_empty() {
+                out += "\n<table>\n";
+                for (key, value) in rows {
+                    out += &format!("<tr><td>{key}</td><td>{value}</td></tr>\n");
+                }
+                out += "</table>\n";
+            }
+            out += "</li>\n";
+        }
+        out += "</ol>\n";
+        if let (true, Some(backtrace)) = (self.backtrace, error.err_backtrace()) {
+            let backtrace = redact(&backtrace.to_string());
+            out += &format!("<h2>Backtrace</h2>\n<pre>{}</pre>\n", escape(&backtrace));
+        }
+        out
+    }
+
+    /// Build a response with the rendered page, and the HTTP status of the
+    /// error's code, or 500 if it has none.
+    #[cfg(feature = "http")]
+    pub fn response(&self, error: &StackError) -> http::Response<String> {
+        let mut response = http::Response::new(self.render(error));
+        *response.status_mut() = http::StatusCode::from_u16(http_status(error))
+            .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        response
+    }
+}
+
+/// Get the HTTP status of the error's code, or 500 if it has none.
+fn http_status(error: &StackError) -> u16 {
+    error
+        .err_code()
+        .copied()
+        .and_then(ErrorCode::to_http_value)
+        .unwrap_or(500)
+}
+
+/// Escape the text for HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&#39;",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error() -> StackError {
+        StackError::from_msg("Base <error>")
+            .with_err_field("path", "a&b.toml")
+            .stack_err_msg("Stacked error")
+            .with_err_code(ErrorCode::HttpNotFound)
+            .with_err_uri("https://example.com/?a=1&b=2".to_string())
+    }
+
+    #[test]
+    fn test_render_details() {
+        let page = HtmlRenderer::new()
+            .with_details(true)
+            .with_backtrace(true)
+            .render(&error());
+        assert!(page.contains("<title>Stacked error</title>"));
+        assert!(page.contains("<span class=\"message\">Base &lt;error&gt;</span>"));
+        assert!(page.contains("<tr><td>path</td><td>a&amp;b.toml</td></tr>"));
+        assert!(page.contains(
+            "<span class=\"message\">Stacked error</span><span class=\"code\">HttpNotFound</span>"
+        ));
+        assert!(page.contains(
+            "<a href=\"https://example.com/?a=1&amp;b=2\">https://example.com/?a=1&amp;b=2</a>"
+        ));
+    }
+
+    #[test]
+    fn test_render_backtrace() {
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let error = StackError::from_panic("panicked".to_string(), Some(backtrace))
+            .stack_err_msg("Stacked error");
+        let page = HtmlRenderer::new()
+            .with_details(true)
+            .with_backtrace(true)
+            .render(&error);
+        assert!(page.contains("<h2>Backtrace</h2>\n<pre>"));
+        let page = HtmlRenderer::new()
+            .with_details(true)
+            .with_backtrace(false)
+            .render(&error);
+        assert!(!page.contains("<h2>Backtrace</h2>"));
+    }
+
+    #[test]
+    fn test_render_without_details() {
+        let page = HtmlRenderer::new().with_details(false).render(&error());
+        assert!(page.contains("<h1>Error 404</h1>"));
+        assert!(!page.contains("Base"));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_response() {
+        let response = HtmlRenderer::new().response(&error());
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Provides the JSON-RPC 2.0 error object, and conversions to and from
+//! [`StackError`].
+//!
+//! The error chain is carried in the `data` member as an [`ErrorPayload`], so
+//! a [`StackError`] can be rebuilt with its codes and URIs on the receiving
+//! side. Error objects without this data are converted to a single frame
+//! with the error message.
+
+use serde::{Deserialize, Serialize};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::payload::ErrorPayload;
+use crate::redact::redact;
+
+/// Invalid JSON was received by the server.
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON sent is not a valid request object.
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method does not exist or is not available.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Invalid method parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+/// Internal JSON-RPC error.
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The first code of the range reserved for implementation-defined server
+/// errors, which ends at `-32099`.
+pub const SERVER_ERROR: i64 = -32000;
+
+/// Whether the code is in the range reserved by the JSON-RPC specification.
+/// Application codes should be outside of it.
+pub fn is_reserved(code: i64) -> bool {
+    (-32768..=-32000).contains(&code)
+}
+
+/// A JSON-RPC 2.0 error object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    /// The error code, mapped from the error code with [`jsonrpc_code`].
+    pub code: i64,
+    /// The redacted message of the top frame.
+    pub message: String,
+    /// The error chain as an [`ErrorPayload`], absent in error objects from
+    /// other implementations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Map an error code to a JSON-RPC error code. Invalid arguments and
+/// unimplemented operations use the matching reserved codes, other codes use
+/// [`SERVER_ERROR`], and errors without a code are internal errors.
+pub fn jsonrpc_code(code: Option<ErrorCode>) -> i64 {
+    let Some(code) = code else {
+        return INTERNAL_ERROR;
+    };
+    match code {
+        ErrorCode::RuntimeInvalidValue
+        | ErrorCode::RuntimeInvalidIndex
+        | ErrorCode::RuntimeInvalidKey
+        | ErrorCode::HttpUnprocessableEntity
+        | ErrorCode::IoInvalidInput => INVALID_PARAMS,
+        ErrorCode::HttpBadRequest => INVALID_REQUEST,
+        ErrorCode::IoInvalidData => PARSE_ERROR,
+        ErrorCode::RuntimeNotImplemented | ErrorCode::HttpNotImplemented => METHOD_NOT_FOUND,
+        _ => SERVER_ERROR,
+    }
+}
+
+impl From<&StackError> for JsonRpcError {
+    fn from(error: &StackError) -> Self {
+        Self {
+            code: jsonrpc_code(error.err_code().copied()),
+            message: redact(&error.frame_msg().to_string()),
+            data: serde_json::to_value(ErrorPayload::from(error)).ok(),
+        }
+    }
+}
+
+/// Error objects without an [`ErrorPayload`] as data have the code mapped
+/// from reserved JSON-RPC codes, and the `jsonrpc_code` field.
+impl From<JsonRpcError> for StackError {
+    fn from(error: JsonRpcError) -> Self {
+        let payload = error
+            .data
+            .and_then(|data| serde_json::from_value::<ErrorPayload>(data).ok())
+            .filter(|payload| !payload.frames.is_empty());
+        if let Some(payload) = payload {
+            return payload.into();
+        }
+        let code = match error.code {
+            PARSE_ERROR => Some(ErrorCode::IoInvalidData),
+            INVALID_REQUEST => Some(ErrorCode::HttpBadRequest),
+            METHOD_NOT_FOUND => Some(ErrorCode::RuntimeNotImplemented),
+            INVALID_PARAMS => Some(ErrorCode::IoInvalidInput),
+            _ => None,
+        };
+        let stacked =
+            StackError::from_msg(error.message).with_err_field("jsonrpc_code", error.code);
+        match code {
+            Some(code) => stacked.with_err_code(code),
+            None => stacked,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonrpc_error_round_trips() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoInvalidInput)
+            .with_err_field("param", "amount")
+            .stack_err_msg("Stacked error");
+        let object = JsonRpcError::from(&error);
+        assert_eq!(object.code, INVALID_PARAMS);
+        assert_eq!(object.message, "Stacked error");
+
+        let json = serde_json::to_string(&object).unwrap();
+        let decoded: StackError = serde_json::from_str::<JsonRpcError>(&json).unwrap().into();
+        assert_eq!(format!("{:?}", decoded), "Base error\nStacked error");
+        assert_eq!(decoded.err_code(), Some(&ErrorCode::IoInvalidInput));
+    }
+
+    #[test]
+    fn test_jsonrpc_error_without_data() {
+        let object: JsonRpcError =
+            serde_json::from_str(r#"{"code": -32601, "message": "Method not found"}"#).unwrap();
+        let error = StackError::from(object);
+        assert_eq!(format!("{:?}", error), "Method not found");
+        assert_eq!(error.err_code(), Some(&ErrorCode::RuntimeNotImplemented));
+        assert_eq!(
+            error.err_fields(),
+            &[("jsonrpc_code".to_string(), "-32601".to_string())]
+        );
+        assert!(is_reserved(SERVER_ERROR) && !is_reserved(-31999));
+    }
+}
@@ -0,0 +1,56 @@
+//! Conversion of `StackError` into `juniper` field errors.
+
+use ::juniper::{FieldError, IntoFieldError, Object, ScalarValue, Value};
+
+use crate::error::{ErrorStacks, StackError};
+use crate::redact::redact;
+
+/// The error message is the top frame's message. The error code, by its
+/// variant name, and the URI are set as the `code` and `uri` extensions when
+/// they are present. The message and URI are passed through the
+/// [redactor](crate::redact), as they are sent to clients.
+impl<S: ScalarValue> IntoFieldError<S> for StackError {
+    fn into_field_error(self) -> FieldError<S> {
+        let mut extensions = Object::with_capacity(2);
+        if let Some(code) = self.err_code() {
+            extensions.add_field("code", Value::scalar(format!("{code:?}")));
+        }
+        if let Some(uri) = self.err_uri() {
+            extensions.add_field("uri", Value::scalar(redact(uri)));
+        }
+        let extensions = if extensions.field_count() > 0 {
+            Value::Object(extensions)
+        } else {
+            Value::Null
+        };
+        FieldError::new(redact(&self.frame_msg().to_string()), extensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+    use ::juniper::{graphql_value, DefaultScalarValue};
+
+    #[test]
+    fn test_into_field_error() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::HttpNotFound)
+            .with_err_uri("https://example.com/errors/not_found".to_string())
+            .stack_err_msg("Stacked error");
+        let field_error: FieldError<DefaultScalarValue> = error.into_field_error();
+        assert_eq!(field_error.message(), "Stacked error");
+        assert_eq!(
+            field_error.extensions(),
+            &graphql_value!({
+                "code": "HttpNotFound",
+                "uri": "https://example.com/errors/not_found",
+            })
+        );
+
+        let field_error: FieldError<DefaultScalarValue> =
+            StackError::from_msg("Base error").into_field_error();
+        assert_eq!(field_error.extensions(), &Value::Null);
+    }
+}
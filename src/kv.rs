@@ -0,0 +1,111 @@
+//! Provides key-value pairs describing an error, for structured logging.
+
+use crate::error::{ErrorStacks, StackError};
+
+/// Key-value pairs describing an error, returned by
+/// [`StackError::kv_pairs`].
+///
+/// The pairs are the `message` of the top frame, the `code`, `uri` and
+/// `location` if set, followed by the fields of all frames from the root to
+/// the top. With the `log` feature, this implements `log::kv::Source` so it
+/// can be attached to log records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorKvPairs(Vec<(String, String)>);
+
+impl ErrorKvPairs {
+    /// Iterate over the key-value pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl IntoIterator for ErrorKvPairs {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(feature = "log")]
+impl log::kv::Source for ErrorKvPairs {
+    fn visit<'kvs>(
+        &'kvs self,
+        visitor: &mut dyn log::kv::VisitSource<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        for (key, value) in self.iter() {
+            visitor.visit_pair(log::kv::Key::from_str(key), log::kv::Value::from(value))?;
+        }
+        Ok(())
+    }
+}
+
+impl StackError {
+    /// Get key-value pairs describing the error, for structured logging.
+    pub fn kv_pairs(&self) -> ErrorKvPairs {
+        let mut pairs = vec![("message".to_string(), self.frame_msg().to_string())];
+        if let Some(code) = self.err_code() {
+            pairs.push(("code".to_string(), format!("{code:?}")));
+        }
+        if let Some(uri) = self.err_uri() {
+            pairs.push(("uri".to_string(), uri.to_string()));
+        }
+        if let Some(location) = self.err_location() {
+            pairs.push(("location".to_string(), location.to_string()));
+        }
+        let mut frames: Vec<_> = std::iter::successors(Some(self), |e| {
+            std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+        })
+        .collect();
+        frames.reverse();
+        for frame in frames {
+            pairs.extend(frame.err_fields().iter().cloned());
+        }
+        ErrorKvPairs(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_kv_pairs() {
+        let line = line!();
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .stack_err_msg("Stacked error")
+            .with_err_field("attempt", 2);
+        let pairs: Vec<_> = error.kv_pairs().into_iter().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("message".to_string(), "Stacked error".to_string()),
+                ("code".to_string(), "IoNotFound".to_string()),
+                ("location".to_string(), format!("src/kv.rs:{}:14", line + 4)),
+                ("path".to_string(), "config.toml".to_string()),
+                ("attempt".to_string(), "2".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_kv_pairs_log_source() {
+        use log::kv::Source;
+
+        let pairs = StackError::from_msg("Test error")
+            .with_err_field("path", "config.toml")
+            .kv_pairs();
+        assert_eq!(pairs.count(), 3);
+        assert_eq!(
+            pairs
+                .get(log::kv::Key::from_str("path"))
+                .map(|v| v.to_string()),
+            Some("config.toml".to_string())
+        );
+    }
+}
@@ -1,25 +1,170 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#![cfg_attr(stackerror_nightly, feature(error_generic_member_access))]
 
+extern crate alloc;
+// Lets the derive macros use `::stackerror` paths inside this crate
+extern crate self as stackerror;
+
+#[cfg(feature = "std")]
+pub mod builder;
+#[cfg(feature = "catalog")]
+pub mod catalog;
+#[cfg(feature = "clap")]
+pub mod cli;
 pub mod codes;
+#[cfg(feature = "codespan")]
+pub mod codespan;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod context;
+// The standard clock panics on wasm32-unknown-unknown
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub mod crash;
+#[cfg(feature = "std")]
+pub mod diagnostics;
+#[cfg(feature = "std")]
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub mod emit;
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "eyre")]
+pub mod eyre;
+#[cfg(feature = "fluent")]
+pub mod fluent;
+#[cfg(feature = "aws")]
+mod from_aws;
+#[cfg(feature = "config")]
+mod from_config;
+#[cfg(feature = "dotenvy")]
+mod from_dotenvy;
+#[cfg(all(feature = "embedded-io", feature = "std"))]
+mod from_embedded_io;
+#[cfg(feature = "error-stack")]
+mod from_error_stack;
 #[cfg(feature = "http")]
 mod from_http;
+#[cfg(feature = "kafka")]
+mod from_kafka;
+#[cfg(feature = "openssl")]
+mod from_openssl;
 #[cfg(feature = "reqwest")]
 mod from_reqwest;
+#[cfg(feature = "json")]
+mod from_serde_json;
+#[cfg(feature = "io")]
 mod from_std_io;
+#[cfg(feature = "std")]
+mod from_std_sync;
+#[cfg(feature = "tokio")]
+mod from_tokio;
+#[cfg(feature = "tungstenite")]
+mod from_tungstenite;
+#[cfg(feature = "ureq")]
+mod from_ureq;
+#[cfg(feature = "futures")]
+pub mod future;
+#[cfg(feature = "http")]
+pub mod headers;
+pub mod hint;
+#[cfg(feature = "html")]
+pub mod html;
+#[cfg(feature = "jsonrpc")]
+pub mod jsonrpc;
+#[cfg(feature = "juniper")]
+mod juniper;
+#[cfg(feature = "std")]
+pub mod kv;
+#[cfg(feature = "std")]
 pub mod macros;
+#[cfg(feature = "std")]
+pub mod mapper;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "nom")]
+pub mod nom;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+pub mod origin;
+#[cfg(feature = "std")]
+pub mod panic;
+#[cfg(feature = "serde")]
+pub mod payload;
+#[cfg(feature = "std")]
 pub mod prelude;
+#[cfg(feature = "io")]
+pub mod process;
+#[cfg(feature = "prost")]
+pub mod proto;
+#[cfg(feature = "std")]
+pub mod redact;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod result;
+pub mod severity;
+#[cfg(feature = "slog")]
+pub mod slog;
+#[cfg(feature = "std")]
+pub mod span;
+#[cfg(feature = "futures")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod testing;
+// The standard clock panics on wasm32-unknown-unknown
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub mod throttle;
+#[cfg(all(
+    feature = "tokio",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub mod time;
+pub mod tiny;
+#[cfg(feature = "tracing")]
+pub mod tracing;
+#[cfg(feature = "std")]
+pub mod uri;
+#[cfg(feature = "std")]
+pub mod util;
+#[cfg(feature = "cbor")]
+pub mod wire;
 
+#[cfg(feature = "std")]
+pub use panic::catch_stack as catch;
+#[cfg(feature = "futures")]
+pub use panic::catch_stack_async as catch_async;
+#[cfg(feature = "std")]
 pub use prelude::*;
-pub use stackerror_impl::derive_stack_error;
+#[cfg(feature = "std")]
+pub use render::set_renderer;
+#[cfg(feature = "std")]
+pub use stackerror_impl::{derive_stack_error, IntoStackError};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_prelude_keeps_core_crate() {
+        // The glob import of the prelude mustn't shadow the `core` crate
+        let (mut a, mut b) = (1, 2);
+        core::mem::swap(&mut a, &mut b);
+        assert_eq!((a, b), (2, 1));
+    }
+
     #[test]
     fn test_error_builds_empty() {
-        let error = StackError::new();
+        let error = StackError::empty();
         assert_eq!(format!("{:?}", error), "");
     }
 
@@ -27,26 +172,168 @@ mod tests {
     fn test_error_builds_from_msg() {
         let error = StackError::from_msg("Test error");
         assert_eq!(format!("{:?}", error), "Test error");
+        let error = StackError::new("Test error");
+        assert_eq!(format!("{:?}", error), "Test error");
     }
 
     #[test]
     fn test_error_has_err() {
-        let error = StackError::new().with_err_msg("Test error");
+        let error = StackError::empty().with_err_msg("Test error");
         assert_eq!(format!("{:?}", error), "Test error");
     }
 
     #[test]
     fn test_error_has_code() {
-        let error = StackError::new().with_err_code(ErrorCode::RuntimeInvalidValue);
+        let error = StackError::empty().with_err_code(ErrorCode::RuntimeInvalidValue);
         assert_eq!(error.err_code(), Some(&ErrorCode::RuntimeInvalidValue));
     }
 
+    #[test]
+    fn test_error_has_codes() {
+        let error = StackError::from_msg("Test error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .stack_err_msg("Stacked error")
+            .stack_err_msg("Handler error")
+            .with_err_code(ErrorCode::HttpNotFound);
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpNotFound));
+        let codes: Vec<_> = error.err_codes().collect();
+        assert_eq!(codes, [&ErrorCode::HttpNotFound, &ErrorCode::IoNotFound]);
+        let error = error.with_no_err_code();
+        assert_eq!(error.err_code(), None);
+        assert_eq!(
+            error.err_codes().collect::<Vec<_>>(),
+            [&ErrorCode::IoNotFound]
+        );
+    }
+
+    #[test]
+    fn test_error_coded_constructors() {
+        let error = StackError::not_found("Test error");
+        assert_eq!(format!("{:?}", error), "Test error");
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpNotFound));
+        let error = StackError::timeout("Test error");
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoTimedOut));
+    }
+
     #[test]
     fn test_error_has_uri() {
-        let error = StackError::new().with_err_uri("https://example.com/error".to_string());
+        let error = StackError::empty().with_err_uri("https://example.com/error".to_string());
         assert_eq!(error.err_uri(), Some("https://example.com/error"));
     }
 
+    #[test]
+    fn test_error_has_fields() {
+        let error = StackError::empty()
+            .with_err_field("key", "value")
+            .with_err_field("count", 3);
+        assert_eq!(
+            error.err_fields(),
+            &[
+                ("key".to_string(), "value".to_string()),
+                ("count".to_string(), "3".to_string())
+            ]
+        );
+        assert!(error.with_no_err_fields().err_fields().is_empty());
+    }
+
+    #[test]
+    fn test_error_has_spans() {
+        let error = StackError::empty()
+            .with_err_span("input.txt", 3..7)
+            .with_err_span("input.txt", 10..12);
+        assert_eq!(
+            error.err_spans(),
+            &[
+                ErrorSpan::new("input.txt", 3..7),
+                ErrorSpan::new("input.txt", 10..12)
+            ]
+        );
+        assert!(error.with_no_err_spans().err_spans().is_empty());
+    }
+
+    #[test]
+    fn test_error_has_position() {
+        let error = StackError::from_msg("Test error").with_err_position(52, 3, 14);
+        assert_eq!(error.err_position(), Some(&ErrorPosition::new(52, 3, 14)));
+        assert_eq!(
+            error.err_position().unwrap().to_string(),
+            "3:14 (offset 52)"
+        );
+        let error = error.stack_err();
+        assert_eq!(error.err_position(), None);
+    }
+
+    #[test]
+    fn test_error_has_hint() {
+        let error = StackError::from_msg("Test error").with_err_hint(RecoveryHint::Reauthenticate);
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::Reauthenticate));
+        let error = error.stack_err_msg("Stacked error");
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::Reauthenticate));
+        assert_eq!(error.with_no_err_hint().err_hint(), None);
+    }
+
+    #[test]
+    fn test_error_has_origin() {
+        let error = StackError::from_msg("Test error").with_err_code(ErrorCode::HttpBadGateway);
+        assert_eq!(error.err_origin(), None);
+        assert_eq!(error.origin(), Some(ErrorOrigin::Upstream));
+        let error = error
+            .with_err_origin(ErrorOrigin::Internal)
+            .stack_err_msg("Stacked error");
+        assert_eq!(error.err_origin(), Some(&ErrorOrigin::Internal));
+        assert_eq!(error.origin(), Some(ErrorOrigin::Internal));
+        assert_eq!(error.with_no_err_origin().err_origin(), None);
+    }
+
+    #[test]
+    fn test_error_has_timing() {
+        let deadline = std::time::Instant::now();
+        let error = StackError::from_msg("Test error")
+            .with_err_elapsed(std::time::Duration::from_millis(950))
+            .with_err_deadline(deadline);
+        assert_eq!(
+            error.err_elapsed(),
+            Some(std::time::Duration::from_millis(950))
+        );
+        assert_eq!(error.err_deadline(), Some(deadline));
+        assert_eq!(error.stack_err_msg("Stacked error").err_elapsed(), None);
+    }
+
+    #[test]
+    fn test_error_has_attachments() {
+        #[derive(Debug, PartialEq)]
+        struct PartialConfig(Vec<&'static str>);
+
+        let error = StackError::from_msg("Base error")
+            .attach(PartialConfig(vec!["name"]))
+            .attach(3_u32)
+            .stack_err_msg("Stacked error")
+            .attach(4_u32);
+        assert_eq!(
+            error.get_attachment::<PartialConfig>(),
+            Some(&PartialConfig(vec!["name"]))
+        );
+        assert_eq!(error.get_attachment::<u32>(), Some(&4));
+        assert_eq!(error.get_attachment::<String>(), None);
+    }
+
+    #[cfg(stackerror_nightly)]
+    #[test]
+    fn test_error_provides_code() {
+        let error = StackError::from_msg("Test error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_position(52, 3, 14);
+        let error: &dyn std::error::Error = &error;
+        assert_eq!(
+            std::error::request_ref::<ErrorCode>(error),
+            Some(&ErrorCode::IoNotFound)
+        );
+        assert_eq!(
+            std::error::request_ref::<ErrorPosition>(error),
+            Some(&ErrorPosition::new(52, 3, 14))
+        );
+    }
+
     #[test]
     fn test_error_stacks() {
         let base_error = StackError::from_msg("Base error")
@@ -61,6 +348,57 @@ mod tests {
         assert_eq!(stacked_error.err_uri(), Some("https://example.com/base"));
     }
 
+    #[test]
+    fn test_error_dedupes_adjacent_frames() {
+        let mut error = StackError::from_msg("Base error");
+        for _ in 0..3 {
+            error = error.stack_or_repeat_err_msg("Retry failed");
+        }
+        let error = error.stack_err_msg("Stacked error");
+        assert_eq!(
+            format!("{:?}", error),
+            "Base error\nRetry failed (x3)\nStacked error"
+        );
+        assert_eq!(error.repeat_count(), 1);
+        let error = error.stack_err_msg("Stacked error");
+        assert_eq!(
+            format!("{:?}", error),
+            "Base error\nRetry failed (x3)\nStacked error\nStacked error"
+        );
+    }
+
+    #[test]
+    fn test_error_fingerprint() {
+        let make = |msg: &'static str| {
+            StackError::from_msg("Base error")
+                .with_err_code(ErrorCode::IoNotFound)
+                .stack_err_msg(msg)
+        };
+        assert_eq!(
+            make("Stacked error").err_fingerprint(),
+            make("Stacked error").err_fingerprint()
+        );
+        assert_ne!(
+            make("Stacked error").err_fingerprint(),
+            make("Other error").err_fingerprint()
+        );
+        assert_ne!(
+            make("Stacked error").err_fingerprint(),
+            make("Stacked error").with_no_err_code().err_fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_error_into_frames() {
+        let frames = StackError::from_msg("Base error")
+            .stack_err_msg("Stacked error")
+            .into_frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(format!("{:?}", frames[0]), "Base error");
+        assert_eq!(format!("{:?}", frames[1]), "Stacked error");
+    }
+
+    #[cfg(feature = "io")]
     #[test]
     fn test_from_std_io_for_stackerror() {
         let io_err = std::io::Error::from(std::io::ErrorKind::NotFound);
@@ -68,6 +406,38 @@ mod tests {
         assert_eq!(err.err_code(), Some(&ErrorCode::IoNotFound));
     }
 
+    #[cfg(feature = "io")]
+    #[test]
+    fn test_stackerror_into_std_io() {
+        let err = StackError::from_msg("Test error").with_err_code(ErrorCode::IoTimedOut);
+        let io_err: std::io::Error = err.into();
+        assert_eq!(io_err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(io_err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<StackError>())
+            .is_some());
+        let io_err: std::io::Error = StackError::from_msg("Test error").into();
+        assert_eq!(io_err.kind(), std::io::ErrorKind::Other);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn test_from_std_io_keeps_os_error() {
+        let err: StackError = std::io::Error::from_raw_os_error(2).into();
+        let err = err.stack_err_msg("Stacked error");
+        assert_eq!(
+            err.downcast_ref::<std::io::Error>()
+                .and_then(|e| e.raw_os_error()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_catch_for_stackerror() {
+        let err = catch(|| -> u32 { panic!("boom") }).unwrap_err();
+        assert_eq!(err.err_code(), Some(&ErrorCode::RuntimePanic));
+    }
+
     #[cfg(feature = "http")]
     #[test]
     fn test_from_http_status_for_stackerror() {
@@ -90,13 +460,75 @@ mod tests {
         assert_eq!(err.err_code(), None);
     }
 
+    #[derive(Debug, PartialEq, IntoStackError)]
+    enum ParseError {
+        Empty,
+        Invalid(char),
+    }
+
+    impl std::fmt::Display for ParseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                ParseError::Empty => write!(f, "empty input"),
+                ParseError::Invalid(c) => write!(f, "invalid character {c}"),
+            }
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    #[test]
+    fn test_from_err_keeps_variant() {
+        let err: StackError = ParseError::Invalid('x').into();
+        let err = err.stack_err_msg("Stacked error");
+        assert_eq!(format!("{:?}", err), "invalid character x\nStacked error");
+        assert_eq!(
+            err.downcast_ref::<ParseError>(),
+            Some(&ParseError::Invalid('x'))
+        );
+        assert_ne!(err.downcast_ref::<ParseError>(), Some(&ParseError::Empty));
+        assert!(err.downcast_ref::<std::io::Error>().is_none());
+    }
+
+    #[derive(Debug)]
+    struct ConfigError(ParseError);
+
+    impl std::fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "invalid config")
+        }
+    }
+
+    impl std::error::Error for ConfigError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_iter_sources_includes_wrapped_chain() {
+        let err =
+            StackError::from_err(ConfigError(ParseError::Empty)).stack_err_msg("Stacked error");
+        let sources: Vec<_> = err.iter_sources().map(|e| e.to_string()).collect();
+        assert_eq!(sources, ["Stacked error", "invalid config", "empty input"]);
+    }
+
+    #[test]
+    fn test_from_err_chain_flattens_sources() {
+        let err = StackError::from_err_chain(ConfigError(ParseError::Empty));
+        assert_eq!(format!("{:?}", err), "empty input\ninvalid config");
+        assert!(err.downcast_ref::<ConfigError>().is_some());
+        let sources: Vec<_> = err.iter_sources().map(|e| e.to_string()).collect();
+        assert_eq!(sources, ["invalid config", "empty input"]);
+    }
+
     // Add this custom error struct
     #[derive_stack_error]
     struct LibError(StackError);
 
     #[test]
     fn test_custom_builds_empty() {
-        let error = LibError::new();
+        let error = LibError::empty();
         assert_eq!(format!("{:?}", error), "");
     }
 
@@ -104,26 +536,126 @@ mod tests {
     fn test_custom_builds_from_msg() {
         let error = LibError::from_msg("Test error");
         assert_eq!(format!("{:?}", error), "Test error");
+        let error = LibError::new("Test error");
+        assert_eq!(format!("{:?}", error), "Test error");
     }
 
     #[test]
     fn test_custom_has_err() {
-        let error = LibError::new().with_err_msg("Test error");
+        let error = LibError::empty().with_err_msg("Test error");
         assert_eq!(format!("{:?}", error), "Test error");
     }
 
+    #[test]
+    fn test_error_has_severity() {
+        let error = StackError::from_msg("Test error").with_err_severity(Severity::Fatal);
+        assert_eq!(error.err_severity(), Some(&Severity::Fatal));
+        let error = error.stack_err_msg("Stacked error");
+        assert_eq!(error.err_severity(), Some(&Severity::Fatal));
+        assert_eq!(error.with_no_err_severity().err_severity(), None);
+        assert!(Severity::Warn < Severity::Error);
+    }
+
+    /// An implementation with only the methods the trait started with.
+    struct MinimalError(Option<ErrorCode>);
+
+    impl ErrorStacks<ErrorCode> for MinimalError {
+        fn err_code(&self) -> Option<&ErrorCode> {
+            self.0.as_ref()
+        }
+        fn with_err_code(self, code: ErrorCode) -> Self {
+            Self(Some(code))
+        }
+        fn with_no_err_code(self) -> Self {
+            Self(None)
+        }
+        fn err_uri(&self) -> Option<&str> {
+            None
+        }
+        fn with_err_uri(self, _uri: String) -> Self {
+            self
+        }
+        fn with_no_err_uri(self) -> Self {
+            self
+        }
+        fn with_err_msg(self, _error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
+            self
+        }
+        fn with_no_err_msg(self) -> Self {
+            self
+        }
+        fn stack_err(self) -> Self {
+            self
+        }
+        fn stack_err_msg(self, _error: impl std::fmt::Display + Send + Sync + 'static) -> Self {
+            self
+        }
+    }
+
+    #[test]
+    fn test_minimal_implementation_uses_defaults() {
+        let error = MinimalError(None)
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .with_err_hint(RecoveryHint::Retry);
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+        assert!(error.err_fields().is_empty());
+        assert_eq!(error.err_hint(), None);
+    }
+
+    #[test]
+    fn test_error_is_retryable() {
+        let error = StackError::from_msg("Test error").with_err_code(ErrorCode::IoTimedOut);
+        assert!(error.is_retryable());
+        let error = error
+            .stack_err_msg("Stacked error")
+            .with_err_retryable(false);
+        assert!(!error.is_retryable());
+        let error = StackError::from_msg("Test error")
+            .with_retry_after(std::time::Duration::from_secs(1))
+            .with_err_retryable(true);
+        assert_eq!(error.retry_after(), Some(std::time::Duration::from_secs(1)));
+        let error = StackError::from_msg("Test error")
+            .with_err_hint(RecoveryHint::Reauthenticate)
+            .with_err_retryable(false);
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::Reauthenticate));
+        assert!(!StackError::not_found("Test error").is_retryable());
+    }
+
+    #[test]
+    fn test_error_has_location() {
+        let at = |error: &StackError| error.err_location().map(|l| (l.file(), l.line()));
+        let line = line!();
+        let result: StackResult<()> = Err(StackError::from_msg("Test error"));
+        let error = result.stack_err_msg("Stacked error").unwrap_err();
+        assert_eq!(at(&error), Some(("src/lib.rs", line + 2)));
+        assert_eq!(at(&error.into_frames()[0]), Some(("src/lib.rs", line + 1)));
+        let error = LibError::from_msg("Test error").into_inner();
+        assert_eq!(at(&error), Some(("src/lib.rs", line + 5)));
+        assert_eq!(at(&StackError::empty()), None);
+    }
+
     #[test]
     fn test_custom_has_code() {
-        let error = LibError::new().with_err_code(ErrorCode::RuntimeInvalidValue);
+        let error = LibError::empty().with_err_code(ErrorCode::RuntimeInvalidValue);
         assert_eq!(error.err_code(), Some(&ErrorCode::RuntimeInvalidValue));
     }
 
     #[test]
     fn test_custom_has_uri() {
-        let error = LibError::new().with_err_uri("https://example.com/error".to_string());
+        let error = LibError::empty().with_err_uri("https://example.com/error".to_string());
         assert_eq!(error.err_uri(), Some("https://example.com/error"));
     }
 
+    #[test]
+    fn test_custom_has_fields() {
+        let error = LibError::empty().with_err_field("key", "value");
+        assert_eq!(
+            error.err_fields(),
+            &[("key".to_string(), "value".to_string())]
+        );
+    }
+
     #[test]
     fn test_custom_stacks() {
         let base_error = LibError::from_msg("Base error")
@@ -138,6 +670,24 @@ mod tests {
         assert_eq!(stacked_error.err_uri(), Some("https://example.com/base"));
     }
 
+    #[test]
+    fn test_custom_derefs_to_inner() {
+        let error = LibError::from_msg("Base error").stack_err_msg("Stacked error");
+        assert!(error.downcast_ref::<std::io::Error>().is_none());
+        assert_eq!(error.as_ref().err_fingerprint(), error.err_fingerprint());
+        let inner: StackError = error.into_inner();
+        assert_eq!(format!("{:?}", inner), "Base error\nStacked error");
+    }
+
+    #[test]
+    fn test_custom_result_alias() {
+        fn fails() -> StackResult<(), LibError> {
+            Err(LibError::from_msg("Test error"))
+        }
+        assert_eq!(format!("{:?}", fails().unwrap_err()), "Test error");
+    }
+
+    #[cfg(feature = "io")]
     #[test]
     fn test_from_std_io_for_custom_error() {
         let io_err = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
@@ -145,6 +695,131 @@ mod tests {
         assert_eq!(err.err_code(), Some(&ErrorCode::IoPermissionDenied));
     }
 
+    mod without_prelude {
+        use crate::IntoStackError;
+
+        #[derive(Debug, IntoStackError)]
+        struct Failure;
+
+        impl std::fmt::Display for Failure {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "failure")
+            }
+        }
+
+        impl std::error::Error for Failure {}
+
+        #[test]
+        fn test_into_stack_error_without_prelude() {
+            let error: crate::StackError = Failure.into();
+            assert!(error.downcast_ref::<Failure>().is_some());
+        }
+    }
+
+    mod shadowed_names {
+        use crate::derive_stack_error;
+        use crate::prelude::base::*;
+
+        // The expansion mustn't resolve these names where it is used
+        #[allow(dead_code)]
+        mod std {}
+        #[allow(dead_code)]
+        struct Option;
+        #[allow(dead_code)]
+        struct String;
+        #[allow(dead_code)]
+        trait Default {}
+        #[allow(dead_code)]
+        trait From {}
+        #[allow(dead_code)]
+        trait Into {}
+        #[allow(dead_code)]
+        trait AsRef {}
+        #[allow(dead_code)]
+        trait Send {}
+        #[allow(dead_code)]
+        trait Sync {}
+
+        #[derive_stack_error]
+        struct LibError(StackError);
+
+        #[test]
+        fn test_custom_with_shadowed_names() {
+            let error = LibError::from_msg("Test error").stack_err_msg("Stacked error");
+            assert_eq!(format!("{}", error), "Stacked error");
+        }
+    }
+
+    mod partial_prelude {
+        use crate::derive_stack_error;
+        use crate::prelude::base::*;
+        use crate::prelude::codes::*;
+
+        #[derive_stack_error]
+        struct LibError(StackError);
+
+        #[test]
+        fn test_custom_with_partial_prelude() {
+            let error = LibError::from_msg("Test error").with_err_code(ErrorCode::IoNotFound);
+            assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+            assert_eq!(format!("{:?}", error), "Test error");
+        }
+    }
+
+    mod custom_codes {
+        use crate::derive_stack_error;
+        use crate::prelude::*;
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum QuotaCode {
+            QuotaExceeded,
+        }
+
+        #[derive_stack_error(code = QuotaCode)]
+        struct QuotaError(StackError<QuotaCode>);
+
+        #[test]
+        fn test_custom_code_type() {
+            let error = QuotaError::from_msg("Base error")
+                .with_err_code(QuotaCode::QuotaExceeded)
+                .stack_err_msg("Stacked error");
+            assert_eq!(error.err_code(), Some(&QuotaCode::QuotaExceeded));
+            assert_eq!(format!("{}", error), "Stacked error");
+            assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+            assert!(error
+                .render(crate::render::Verbosity::Full)
+                .starts_with("Base error\nStacked error\n\ncode: QuotaExceeded\nlocation: "));
+            let frames = error.into_inner().into_frames();
+            assert_eq!(frames[0].err_code(), Some(&QuotaCode::QuotaExceeded));
+        }
+    }
+
     // NOTE: don't need to test other from impls in custom error since they
     // are handled by a generic impl block
+
+    #[derive_stack_error]
+    struct AppError(LibError);
+
+    #[test]
+    fn test_custom_wraps_custom() {
+        fn fails() -> StackResult<(), LibError> {
+            Err(LibError::from_msg("Base error").with_err_code(ErrorCode::IoNotFound))
+        }
+        fn stacks() -> StackResult<(), AppError> {
+            fails()?;
+            Ok(())
+        }
+        let error = stacks().unwrap_err().stack_err_msg("Stacked error");
+        assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+        let inner: LibError = error.into_inner();
+        assert_eq!(inner.into_inner().frame_msg().to_string(), "Stacked error");
+
+        #[cfg(feature = "io")]
+        {
+            let io_err = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+            let err: AppError = io_err.into();
+            assert_eq!(err.err_code(), Some(&ErrorCode::IoPermissionDenied));
+        }
+    }
 }
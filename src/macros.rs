@@ -1,23 +1,333 @@
-//! Provides a macro for formatting error messages with file and line information.
+//! Provides macros for formatting error messages with file and line
+//! information, for validating values, and for checking the frames of errors
+//! in tests.
+
+// Macros format through this path rather than the prelude, so that they also
+// expand in `#![no_std]` crates and where `format!` is shadowed.
+#[doc(hidden)]
+pub use alloc::format as __format;
 
 /// Formats a string using `format!`, and prefixes it with the file name and
-/// line number.
+/// line number, unless location capture is disabled in the
+/// [`StackErrorConfig`](crate::config::StackErrorConfig).
+///
+/// If the `STACKERROR_PATH_PREFIX` environment variable is set when the
+/// calling crate is built, it is stripped from the file name. This keeps
+/// absolute paths, such as those of dependencies or of builds outside a
+/// workspace, out of error messages. Paths remapped with rustc's
+/// `--remap-path-prefix` are also used as remapped.
 #[macro_export]
 macro_rules! fmt_loc {
     ($($arg:tt)*) => {{
-        format!("{}:{} {}",
-            file!(),
-            line!(),
-            format!($($arg)*)
-        )
+        if $crate::config::config().location {
+            $crate::macros::__format!("{}:{} {}",
+                $crate::macros::strip_path_prefix(
+                    ::core::file!(),
+                    ::core::option_env!("STACKERROR_PATH_PREFIX"),
+                ),
+                ::core::line!(),
+                $crate::macros::__format!($($arg)*)
+            )
+        } else {
+            $crate::macros::__format!($($arg)*)
+        }
     }}
 }
 
+/// Creates a [`StackError`](crate::StackError) with a message formatted
+/// with [`fmt_loc!`], and optionally an error code:
+///
+/// ```rust
+/// return Err(stack_err!(ErrorCode::HttpConflict, "duplicate id {}", id));
+/// ```
+#[macro_export]
+macro_rules! stack_err {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::StackError::from_msg($crate::fmt_loc!($fmt $(, $arg)*))
+    };
+    ($code:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        <$crate::StackError as $crate::ErrorStacks<$crate::ErrorCode>>::with_err_code(
+            $crate::stack_err!($fmt $(, $arg)*),
+            $code,
+        )
+    };
+}
+
+/// Creates a closure which converts an error into a
+/// [`StackError`](crate::StackError) and stacks a frame with a message
+/// formatted with [`fmt_loc!`], and optionally an error code. This is meant to
+/// be used with [`Result::map_err`]:
+///
+/// ```rust
+/// let data = std::fs::read(&path).map_err(stack!(ErrorCode::IoNotFound, "reading {}", path))?;
+/// ```
+#[macro_export]
+macro_rules! stack {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        |error| {
+            <$crate::StackError as $crate::ErrorStacks<$crate::ErrorCode>>::stack_err_msg(
+                $crate::StackError::from(error),
+                $crate::fmt_loc!($fmt $(, $arg)*),
+            )
+        }
+    };
+    ($code:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        |error| {
+            <$crate::StackError as $crate::ErrorStacks<$crate::ErrorCode>>::with_err_code(
+                $crate::stack!($fmt $(, $arg)*)(error),
+                $code,
+            )
+        }
+    };
+}
+
+/// Strip the prefix from the path, along with any leading separator. The path
+/// is returned unchanged if it doesn't start with the prefix.
+#[doc(hidden)]
+pub fn strip_path_prefix<'a>(path: &'a str, prefix: Option<&str>) -> &'a str {
+    match prefix.and_then(|prefix| path.strip_prefix(prefix)) {
+        Some(stripped) => stripped.trim_start_matches(['/', '\\']),
+        None => path,
+    }
+}
+
+/// Returns an error if the two values are not equal.
+///
+/// The error has the [`RuntimeInvalidValue`](crate::ErrorCode::RuntimeInvalidValue)
+/// code, a message prefixed with the location, and `left` and `right` fields
+/// with the `Debug` representation of the values. A custom message can be
+/// given as with `format!`:
+///
+/// ```rust
+/// ensure_eq!(header.version, 2, "unsupported version");
+/// ```
+#[macro_export]
+macro_rules! ensure_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::ensure_eq!(
+            $left,
+            $right,
+            "`{}` == `{}` failed",
+            stringify!($left),
+            stringify!($right)
+        )
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    return Err($crate::__ensure_error!(left, right, $($arg)+).into());
+                }
+            }
+        }
+    };
+}
+
+/// Returns an error if the two values are equal. See [`ensure_eq!`].
+#[macro_export]
+macro_rules! ensure_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::ensure_ne!(
+            $left,
+            $right,
+            "`{}` != `{}` failed",
+            stringify!($left),
+            stringify!($right)
+        )
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left == *right {
+                    return Err($crate::__ensure_error!(left, right, $($arg)+).into());
+                }
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ensure_error {
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        <$crate::StackError as $crate::ErrorStacks<$crate::ErrorCode>>::with_err_field(
+            <$crate::StackError as $crate::ErrorStacks<$crate::ErrorCode>>::with_err_field(
+                <$crate::StackError as $crate::ErrorStacks<$crate::ErrorCode>>::with_err_code(
+                    $crate::StackError::from_msg($crate::fmt_loc!($($arg)+)),
+                    $crate::ErrorCode::RuntimeInvalidValue,
+                ),
+                "left",
+                $crate::macros::__format!("{:?}", $left),
+            ),
+            "right",
+            $crate::macros::__format!("{:?}", $right),
+        )
+    };
+}
+
+/// Asserts that the frames of a [`StackError`](crate::StackError) contain
+/// the given strings in order, from the root to the top.
+///
+/// Each string must be contained in the message of a distinct frame, but
+/// frames in between are allowed:
+///
+/// ```rust
+/// assert_stack_order!(err, ["opening file", "loading config", "startup"]);
+/// ```
+#[macro_export]
+macro_rules! assert_stack_order {
+    ($err:expr, [$($msg:expr),* $(,)?] $(,)?) => {{
+        let error: &$crate::StackError = &$err;
+        let expected: &[&str] = &[$($msg),*];
+        let mut frames: ::std::vec::Vec<::std::string::String> = ::std::iter::successors(Some(error), |e| {
+            ::std::error::Error::source(*e).and_then(|s| s.downcast_ref::<$crate::StackError>())
+        })
+        .map(|frame| frame.frame_msg().to_string())
+        .collect();
+        frames.reverse();
+        let mut remaining = frames.iter();
+        let found = expected
+            .iter()
+            .all(|msg| remaining.any(|frame| frame.contains(msg)));
+        assert!(
+            found,
+            "frames {:?} don't contain {:?} in order",
+            frames,
+            expected
+        );
+    }};
+}
+
+/// Asserts that two [`StackError`](crate::StackError)s have the same frames,
+/// comparing their messages, codes, URIs and fields. On failure, the
+/// frame-by-frame differences from [`diff_errors`](crate::testing::diff_errors)
+/// are shown:
+///
+/// ```rust
+/// assert_errors_eq!(expected, load_config(&path).unwrap_err());
+/// ```
+#[macro_export]
+macro_rules! assert_errors_eq {
+    ($expected:expr, $actual:expr $(,)?) => {{
+        if let ::core::option::Option::Some(diff) =
+            $crate::testing::diff_errors(&$expected, &$actual)
+        {
+            ::core::panic!("errors are not equal:\n{}", diff);
+        }
+    }};
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::prelude::*;
+
     #[test]
     fn test_fmt_lo() {
         let msg = fmt_loc!("Error {} occurred", 42);
-        assert_eq!(msg, format!("src/macros.rs:20 Error 42 occurred"));
+        assert_eq!(msg, format!("src/macros.rs:227 Error 42 occurred"));
+    }
+
+    #[test]
+    fn test_fmt_loc_without_prelude_format() {
+        // As in `#![no_std]` crates, where `format!` isn't in the prelude
+        #[allow(unused_macros)]
+        macro_rules! format {
+            ($($arg:tt)*) => {
+                compile_error!("the prelude format! was used")
+            };
+        }
+        let msg = fmt_loc!("Error {}", 42);
+        assert!(msg.ends_with(" Error 42"));
+    }
+
+    #[test]
+    fn test_stack_err() {
+        let id = 7;
+        let error = stack_err!(ErrorCode::HttpConflict, "duplicate id {}", id);
+        assert!(error.to_string().ends_with(" duplicate id 7"));
+        assert!(error.to_string().starts_with("src/macros.rs:"));
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpConflict));
+        assert_eq!(stack_err!("no code").err_code(), None);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn test_stack() {
+        let result: StackResult<()> = Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .map_err(stack!(ErrorCode::HttpNotFound, "reading {}", "config.toml"));
+        let error = result.unwrap_err();
+        assert!(error.to_string().ends_with(" reading config.toml"));
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpNotFound));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn test_strip_path_prefix() {
+        let path = "/home/dev/app/src/main.rs";
+        assert_eq!(
+            strip_path_prefix(path, Some("/home/dev/app")),
+            "src/main.rs"
+        );
+        assert_eq!(
+            strip_path_prefix(path, Some("/home/dev/app/")),
+            "src/main.rs"
+        );
+        assert_eq!(strip_path_prefix(path, Some("/opt")), path);
+        assert_eq!(strip_path_prefix(path, None), path);
+    }
+
+    #[test]
+    fn test_assert_stack_order() {
+        let error = StackError::from_msg("error opening file")
+            .stack_err_msg("error parsing config")
+            .stack_err_msg("error loading config")
+            .stack_err_msg("error during startup");
+        assert_stack_order!(error, ["opening file", "loading config", "startup"]);
+        assert_stack_order!(error, []);
+        assert!(error.contains_msg("parsing"));
+        assert!(!error.contains_msg("shutdown"));
+    }
+
+    fn check_version(version: u32) -> StackResult<()> {
+        ensure_ne!(version, 0, "version {} is unsupported", version);
+        ensure_eq!(version, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ensure_eq() {
+        assert!(check_version(2).is_ok());
+        let error = check_version(1).unwrap_err();
+        assert!(error.to_string().ends_with(" `version` == `2` failed"));
+        assert_eq!(error.err_code(), Some(&ErrorCode::RuntimeInvalidValue));
+        assert_eq!(
+            error.err_fields(),
+            &[
+                ("left".to_string(), "1".to_string()),
+                ("right".to_string(), "2".to_string())
+            ]
+        );
+        let error = check_version(0).unwrap_err();
+        assert!(error.to_string().ends_with(" version 0 is unsupported"));
+    }
+
+    #[test]
+    #[should_panic(expected = "errors are not equal:\nframe 1: unexpected frame")]
+    fn test_assert_errors_eq_fails() {
+        let expected = StackError::from_msg("Base error");
+        assert_errors_eq!(expected, StackError::from_msg("Base error"));
+        assert_errors_eq!(
+            expected,
+            StackError::from_msg("Base error").stack_err_msg("Stacked error")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "in order")]
+    fn test_assert_stack_order_fails() {
+        let error =
+            StackError::from_msg("error opening file").stack_err_msg("error during startup");
+        assert_stack_order!(error, ["startup", "opening file"]);
     }
 }
@@ -0,0 +1,91 @@
+//! Provides the [`CodeMapper`] policy used to classify foreign errors.
+//!
+//! The `From` conversions for foreign errors, such as `std::io::Error` and
+//! `http::StatusCode`, set the error code using the global mapper. It
+//! defaults to [`DefaultCodeMapper`], and can be replaced with
+//! [`set_code_mapper`] to control the classification across the application.
+
+use std::sync::{Arc, RwLock};
+
+use crate::codes::ErrorCode;
+
+/// Policy mapping foreign error classifications to error codes.
+///
+/// Each method defaults to the mapping of the corresponding [`ErrorCode`]
+/// constructor, so implementations only need to override what they change:
+///
+/// ```rust
+/// struct Mapper;
+///
+/// impl CodeMapper for Mapper {
+///     fn map_http_status(&self, status: u16) -> Option<ErrorCode> {
+///         match status {
+///             500..=599 => Some(ErrorCode::HttpInternalServerError),
+///             _ => DefaultCodeMapper.map_http_status(status),
+///         }
+///     }
+/// }
+///
+/// set_code_mapper(Mapper);
+/// ```
+pub trait CodeMapper: Send + Sync {
+    /// Map an IO error kind to a code.
+    #[cfg(feature = "io")]
+    fn map_io_kind(&self, kind: std::io::ErrorKind) -> Option<ErrorCode> {
+        ErrorCode::from_io_kind(kind)
+    }
+
+    /// Map an HTTP status to a code.
+    fn map_http_status(&self, status: u16) -> Option<ErrorCode> {
+        ErrorCode::from_http_value(status)
+    }
+}
+
+/// The default [`CodeMapper`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultCodeMapper;
+
+impl CodeMapper for DefaultCodeMapper {}
+
+static MAPPER: RwLock<Option<Arc<dyn CodeMapper>>> = RwLock::new(None);
+
+/// Set the global code mapper, replacing any previous one.
+pub fn set_code_mapper(mapper: impl CodeMapper + 'static) {
+    *MAPPER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(mapper));
+}
+
+/// Get the global code mapper.
+pub fn code_mapper() -> Arc<dyn CodeMapper> {
+    MAPPER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| Arc::new(DefaultCodeMapper))
+}
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+    use super::*;
+    use crate::error::{ErrorStacks, StackError};
+
+    struct Mapper;
+
+    impl CodeMapper for Mapper {
+        fn map_io_kind(&self, kind: std::io::ErrorKind) -> Option<ErrorCode> {
+            match kind {
+                std::io::ErrorKind::StorageFull => Some(ErrorCode::IoOutOfMemory),
+                _ => DefaultCodeMapper.map_io_kind(kind),
+            }
+        }
+    }
+
+    #[test]
+    fn test_code_mapper_classifies_io() {
+        let error = || StackError::from(std::io::Error::from(std::io::ErrorKind::StorageFull));
+        assert_eq!(error().err_code(), None);
+        set_code_mapper(Mapper);
+        assert_eq!(error().err_code(), Some(&ErrorCode::IoOutOfMemory));
+        set_code_mapper(DefaultCodeMapper);
+        assert_eq!(error().err_code(), None);
+    }
+}
@@ -0,0 +1,153 @@
+//! Conversions from `async-nats` errors into `StackError`, and the
+//! [`subject_err`] helper to record the subject of a failed operation.
+
+use async_nats::client::{PublishErrorKind, RequestErrorKind, SubscribeErrorKind};
+use async_nats::error::Error;
+use async_nats::ConnectErrorKind;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::hint::RecoveryHint;
+use crate::mapper::code_mapper;
+
+/// An `async-nats` error kind which can be mapped to an error code and a
+/// recovery hint.
+trait Classify {
+    fn classify(&self) -> (Option<ErrorCode>, Option<RecoveryHint>);
+}
+
+impl Classify for ConnectErrorKind {
+    fn classify(&self) -> (Option<ErrorCode>, Option<RecoveryHint>) {
+        match self {
+            Self::ServerParse => (Some(ErrorCode::IoInvalidInput), Some(RecoveryHint::GiveUp)),
+            Self::Dns => (Some(ErrorCode::IoNotFound), Some(RecoveryHint::Retry)),
+            Self::Authentication => (
+                Some(ErrorCode::HttpUnauthorized),
+                Some(RecoveryHint::Reauthenticate),
+            ),
+            Self::AuthorizationViolation => {
+                (Some(ErrorCode::HttpForbidden), Some(RecoveryHint::GiveUp))
+            }
+            Self::TimedOut => (Some(ErrorCode::IoTimedOut), Some(RecoveryHint::Retry)),
+            Self::Tls => (Some(ErrorCode::IoConnectionAborted), None),
+            Self::Io => (
+                Some(ErrorCode::IoConnectionRefused),
+                Some(RecoveryHint::Retry),
+            ),
+            Self::MaxReconnects => (Some(ErrorCode::IoNotConnected), Some(RecoveryHint::GiveUp)),
+        }
+    }
+}
+
+impl Classify for RequestErrorKind {
+    fn classify(&self) -> (Option<ErrorCode>, Option<RecoveryHint>) {
+        match self {
+            Self::TimedOut => (Some(ErrorCode::IoTimedOut), Some(RecoveryHint::Retry)),
+            Self::NoResponders => (
+                Some(ErrorCode::HttpServiceUnavailable),
+                Some(RecoveryHint::Retry),
+            ),
+            Self::InvalidSubject => (Some(ErrorCode::IoInvalidInput), Some(RecoveryHint::GiveUp)),
+            Self::MaxPayloadExceeded => (
+                Some(ErrorCode::HttpPayloadTooLarge),
+                Some(RecoveryHint::GiveUp),
+            ),
+            Self::Other => (None, None),
+        }
+    }
+}
+
+impl Classify for PublishErrorKind {
+    fn classify(&self) -> (Option<ErrorCode>, Option<RecoveryHint>) {
+        match self {
+            Self::MaxPayloadExceeded => (
+                Some(ErrorCode::HttpPayloadTooLarge),
+                Some(RecoveryHint::GiveUp),
+            ),
+            Self::InvalidSubject => (Some(ErrorCode::IoInvalidInput), Some(RecoveryHint::GiveUp)),
+            Self::Send => (Some(ErrorCode::IoBrokenPipe), Some(RecoveryHint::Retry)),
+        }
+    }
+}
+
+impl Classify for SubscribeErrorKind {
+    fn classify(&self) -> (Option<ErrorCode>, Option<RecoveryHint>) {
+        match self {
+            Self::InvalidSubject | Self::InvalidQueueName => {
+                (Some(ErrorCode::IoInvalidInput), Some(RecoveryHint::GiveUp))
+            }
+            Self::Other => (None, None),
+        }
+    }
+}
+
+/// Connect, request, publish and subscribe errors have a code and a recovery
+/// hint for their kind. Connection timeouts and missing responders can be
+/// retried, while invalid subjects and oversized payloads can't. The code of
+/// IO failures is mapped from the IO error kind when it is known.
+impl<K> From<Error<K>> for StackError
+where
+    K: Classify + Clone + PartialEq + std::fmt::Debug + std::fmt::Display + Send + Sync + 'static,
+{
+    fn from(error: Error<K>) -> Self {
+        let (mut code, hint) = error.kind().classify();
+        if let Some(io_code) = std::error::Error::source(&error)
+            .and_then(|e| e.downcast_ref::<std::io::Error>())
+            .and_then(|e| code_mapper().map_io_kind(e.kind()))
+        {
+            code = Some(io_code);
+        }
+        let mut stacked = StackError::from_err(error);
+        if let Some(code) = code {
+            stacked = stacked.with_err_code(code);
+        }
+        if let Some(hint) = hint {
+            stacked = stacked.with_err_hint(hint);
+        }
+        stacked
+    }
+}
+
+/// Creates a closure which converts an error into a [`StackError`] with the
+/// subject as the `subject` field. This is meant to be used with
+/// [`Result::map_err`]:
+///
+/// ```rust
+/// let reply = client
+///     .request(subject.clone(), payload)
+///     .await
+///     .map_err(nats::subject_err(&subject))?;
+/// ```
+pub fn subject_err<E>(subject: impl std::fmt::Display) -> impl FnOnce(E) -> StackError
+where
+    StackError: From<E>,
+{
+    move |error| StackError::from(error).with_err_field("subject", subject)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_nats::RequestError;
+
+    #[test]
+    fn test_nats_request_error() {
+        let result: Result<(), RequestError> = Err(RequestErrorKind::NoResponders.into());
+        let error = result.map_err(subject_err("orders.create")).unwrap_err();
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpServiceUnavailable));
+        assert_eq!(error.err_hint(), Some(&RecoveryHint::Retry));
+        assert_eq!(
+            error.err_fields(),
+            &[("subject".to_string(), "orders.create".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_nats_connect_io_error() {
+        let error = StackError::from(async_nats::ConnectError::with_source(
+            ConnectErrorKind::Io,
+            std::io::Error::from(std::io::ErrorKind::ConnectionReset),
+        ));
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoConnectionReset));
+    }
+}
@@ -0,0 +1,106 @@
+//! Provides conversions from `nom` parser errors into `StackError`.
+
+use nom_language::error::{VerboseError, VerboseErrorKind};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+/// Parser errors which can be converted into frames.
+pub trait NomFrames<'a> {
+    /// Get the remaining input and message of each frame, from the innermost
+    /// parser to the outermost.
+    fn nom_frames(&self) -> Vec<(&'a str, String)>;
+}
+
+impl<'a> NomFrames<'a> for nom::error::Error<&'a str> {
+    fn nom_frames(&self) -> Vec<(&'a str, String)> {
+        vec![(self.input, self.code.description().to_string())]
+    }
+}
+
+impl<'a> NomFrames<'a> for VerboseError<&'a str> {
+    fn nom_frames(&self) -> Vec<(&'a str, String)> {
+        self.errors
+            .iter()
+            .map(|(input, kind)| {
+                let message = match kind {
+                    VerboseErrorKind::Context(context) => context.to_string(),
+                    VerboseErrorKind::Char(c) => format!("expected '{c}'"),
+                    VerboseErrorKind::Nom(kind) => kind.description().to_string(),
+                };
+                (*input, message)
+            })
+            .collect()
+    }
+}
+
+/// Convert a `nom` error into a [`StackError`] with the `IoInvalidData` code.
+///
+/// Each error of the parser, including the contexts added with
+/// `nom::error::context` when using [`VerboseError`], is stacked as a frame
+/// from the innermost parser to the outermost. Each frame has a span in
+/// `source_name`, at the position in `input` where the error occurred.
+/// Incomplete input is converted to a single frame with the
+/// `IoUnexpectedEof` code.
+pub fn from_nom<'a, E: NomFrames<'a>>(
+    source_name: &str,
+    input: &'a str,
+    error: nom::Err<E>,
+) -> StackError {
+    let error = match error {
+        nom::Err::Incomplete(_) => {
+            return StackError::from_msg("incomplete input")
+                .with_err_code(ErrorCode::IoUnexpectedEof)
+                .with_err_span(source_name, input.len()..input.len());
+        }
+        nom::Err::Error(error) | nom::Err::Failure(error) => error,
+    };
+    let mut stacked: Option<StackError> = None;
+    for (remaining, message) in error.nom_frames() {
+        let offset = input.len().saturating_sub(remaining.len());
+        let end = offset + remaining.chars().next().map_or(0, char::len_utf8);
+        let frame = match stacked {
+            Some(stacked) => stacked.stack_err_msg(message),
+            None => StackError::from_msg(message),
+        };
+        stacked = Some(frame.with_err_span(source_name, offset..end));
+    }
+    stacked
+        .unwrap_or_default()
+        .with_err_code(ErrorCode::IoInvalidData)
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::bytes::complete::tag;
+    use nom::character::complete::digit1;
+    use nom::error::context;
+    use nom::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_from_nom_verbose() {
+        let input = "version: x";
+        let error = context("version", (tag("version: "), digit1))
+            .parse(input)
+            .unwrap_err();
+        let error = from_nom::<VerboseError<&str>>("config", input, error);
+        assert_eq!(format!("{:?}", error), "Digit\nversion");
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidData));
+        assert_eq!(error.err_spans()[0].range, 0..1);
+        let source = std::error::Error::source(&error)
+            .and_then(|e| e.downcast_ref::<StackError>())
+            .unwrap();
+        assert_eq!(source.err_spans()[0].range, 9..10);
+    }
+
+    #[test]
+    fn test_from_nom_simple() {
+        let input = "x";
+        let error = digit1::<_, nom::error::Error<&str>>(input).unwrap_err();
+        let error = from_nom("config", input, error);
+        assert_eq!(format!("{:?}", error), "Digit");
+        assert_eq!(error.err_spans()[0].range, 0..1);
+    }
+}
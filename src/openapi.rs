@@ -0,0 +1,94 @@
+//! Provides helpers for documenting error responses in OpenAPI documents.
+
+use serde_json::{json, Map, Value};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::payload::ErrorPayload;
+
+/// The HTTP status used in responses for an error code. Codes without an
+/// HTTP value are reported as internal server errors.
+fn response_status(code: ErrorCode) -> u16 {
+    ErrorCode::to_http_value(code).unwrap_or(500)
+}
+
+/// Get the JSON schema of the error body, to be registered in the document's
+/// `components/schemas` under the `ErrorPayload` name.
+pub fn openapi_error_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(ErrorPayload)).expect("the schema is serializable")
+}
+
+/// Build the OpenAPI response objects for the error codes a handler can
+/// return, keyed by HTTP status.
+///
+/// Codes sharing a status are grouped in one response, with one example
+/// body per code. The responses refer to the error body schema as
+/// `#/components/schemas/ErrorPayload` (see [`openapi_error_schema`]).
+///
+/// ```rust
+/// let responses = openapi_error_responses(&[ErrorCode::HttpNotFound, ErrorCode::HttpConflict]);
+/// ```
+pub fn openapi_error_responses(codes: &[ErrorCode]) -> Map<String, Value> {
+    let mut grouped: Vec<(u16, Vec<ErrorCode>)> = Vec::new();
+    for code in codes {
+        let status = response_status(*code);
+        match grouped.iter_mut().find(|(s, _)| *s == status) {
+            Some((_, group)) if group.contains(code) => {}
+            Some((_, group)) => group.push(*code),
+            None => grouped.push((status, vec![*code])),
+        }
+    }
+    grouped.sort_by_key(|(status, _)| *status);
+
+    let mut responses = Map::new();
+    for (status, group) in grouped {
+        let names: Vec<_> = group.iter().map(|code| format!("{code:?}")).collect();
+        let mut examples = Map::new();
+        for (code, name) in group.iter().zip(&names) {
+            let body = StackError::from_msg(name.clone()).with_err_code(*code);
+            let body = serde_json::to_value(&body).expect("errors are serializable");
+            examples.insert(name.clone(), json!({ "value": body }));
+        }
+        responses.insert(
+            status.to_string(),
+            json!({
+                "description": names.join(", "),
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ErrorPayload" },
+                        "examples": examples,
+                    }
+                }
+            }),
+        );
+    }
+    responses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_responses_grouped_by_status() {
+        let responses = openapi_error_responses(&[
+            ErrorCode::HttpNotFound,
+            ErrorCode::RuntimeInvalidValue,
+            ErrorCode::HttpInternalServerError,
+            ErrorCode::HttpNotFound,
+        ]);
+        let statuses: Vec<_> = responses.keys().cloned().collect();
+        assert_eq!(statuses, vec!["404", "500"]);
+        assert_eq!(
+            responses["500"]["description"],
+            "RuntimeInvalidValue, HttpInternalServerError"
+        );
+        let example = &responses["404"]["content"]["application/json"]["examples"]["HttpNotFound"];
+        assert_eq!(example["value"]["frames"][0]["code"], "HttpNotFound");
+    }
+
+    #[test]
+    fn test_error_schema_has_title() {
+        assert_eq!(openapi_error_schema()["title"], "ErrorPayload");
+    }
+}
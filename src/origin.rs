@@ -0,0 +1,79 @@
+//! Provides the [`ErrorOrigin`] type, telling who is to blame for an error.
+
+use crate::codes::ErrorCode;
+
+/// Who is to blame for an error, e.g. to keep caller mistakes out of SLO
+/// accounting, and to alert on dependency failures separately from bugs.
+///
+/// Origins are set with
+/// [`ErrorStacks::with_err_origin`](crate::error::ErrorStacks::with_err_origin)
+/// and are inherited by stacked frames, as are codes. Errors without an
+/// origin can have one derived from their code with
+/// [`ErrorOrigin::from_code`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ErrorOrigin {
+    /// The caller made a mistake, e.g. an invalid request.
+    User,
+    /// The program itself failed, e.g. a bug.
+    Internal,
+    /// A dependency failed, e.g. a remote service or the network.
+    Upstream,
+}
+
+impl ErrorOrigin {
+    /// Derive the origin from the category of a code: HTTP 4xx and invalid
+    /// input codes are [`User`](Self::User) errors, HTTP 500 and 501, panics
+    /// and unimplemented operations are [`Internal`](Self::Internal), and
+    /// gateway and connection codes are [`Upstream`](Self::Upstream). Other
+    /// codes, such as IO codes which depend on what failed, have no origin.
+    pub fn from_code(code: ErrorCode) -> Option<Self> {
+        match code {
+            ErrorCode::RuntimeNotImplemented
+            | ErrorCode::RuntimePanic
+            | ErrorCode::HttpInternalServerError
+            | ErrorCode::HttpNotImplemented
+            | ErrorCode::IoOutOfMemory => Some(Self::Internal),
+            ErrorCode::RuntimeProcessFailed
+            | ErrorCode::HttpFailedDependency
+            | ErrorCode::HttpBadGateway
+            | ErrorCode::HttpServiceUnavailable
+            | ErrorCode::HttpGatewayTimeout
+            | ErrorCode::HttpNetworkAuthenticationRequired
+            | ErrorCode::IoConnectionRefused
+            | ErrorCode::IoConnectionReset
+            | ErrorCode::IoConnectionAborted
+            | ErrorCode::IoNotConnected
+            | ErrorCode::IoBrokenPipe
+            | ErrorCode::IoTimedOut => Some(Self::Upstream),
+            ErrorCode::IoInvalidInput => Some(Self::User),
+            code => match ErrorCode::to_http_value(code) {
+                Some(400..=499) => Some(Self::User),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_from_code() {
+        assert_eq!(
+            ErrorOrigin::from_code(ErrorCode::HttpTooManyRequests),
+            Some(ErrorOrigin::User)
+        );
+        assert_eq!(
+            ErrorOrigin::from_code(ErrorCode::HttpFailedDependency),
+            Some(ErrorOrigin::Upstream)
+        );
+        assert_eq!(
+            ErrorOrigin::from_code(ErrorCode::RuntimePanic),
+            Some(ErrorOrigin::Internal)
+        );
+        assert_eq!(ErrorOrigin::from_code(ErrorCode::IoNotFound), None);
+    }
+}
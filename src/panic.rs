@@ -0,0 +1,166 @@
+//! Provides helpers for converting panics into [`StackError`] values.
+
+use std::any::Any;
+#[cfg(not(target_arch = "wasm32"))]
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::RefCell;
+use std::panic::UnwindSafe;
+use std::sync::Once;
+
+use crate::error::{ErrorStacks, StackError};
+
+/// Details recorded by the panic hook for the last panic on this thread.
+struct PanicDetails {
+    location: Option<String>,
+    backtrace: Option<std::backtrace::Backtrace>,
+}
+
+impl PanicDetails {
+    /// Record the location and backtrace of the panic being handled by a hook.
+    fn capture(info: &std::panic::PanicHookInfo<'_>) -> Self {
+        Self {
+            location: info.location().map(|l| l.to_string()),
+            backtrace: capture_std_backtrace(),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<PanicDetails>> = const { RefCell::new(None) };
+}
+
+/// Install a panic hook which records the location and backtrace of panics,
+/// so that [`catch_stack`] can include them in the error. The previously
+/// installed hook is still called. Only the first call installs the hook.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let details = PanicDetails::capture(info);
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(details));
+            previous(info);
+        }));
+    });
+}
+
+/// Capture a backtrace if enabled in the
+/// [`StackErrorConfig`](crate::config::StackErrorConfig), or with
+/// `RUST_BACKTRACE` if it isn't set there.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn capture_std_backtrace() -> Option<Backtrace> {
+    let backtrace = match crate::config::config().backtrace {
+        Some(true) => Backtrace::force_capture(),
+        Some(false) => return None,
+        None => Backtrace::capture(),
+    };
+    match backtrace.status() {
+        BacktraceStatus::Captured => Some(backtrace),
+        _ => None,
+    }
+}
+
+/// Backtraces aren't supported on WebAssembly.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn capture_std_backtrace() -> Option<std::backtrace::Backtrace> {
+    None
+}
+
+/// Run a closure, converting a panic into a [`StackError`] with the
+/// [`ErrorCode::RuntimePanic`](crate::codes::ErrorCode::RuntimePanic) code.
+///
+/// The panic payload is used as the error message. If [`install_panic_hook`]
+/// was called, the panic location is added as the `location` field, and the
+/// backtrace is attached, see [`StackError::err_backtrace`].
+pub fn catch_stack<T>(f: impl FnOnce() -> T + UnwindSafe) -> Result<T, StackError> {
+    LAST_PANIC.with(|last| last.borrow_mut().take());
+    std::panic::catch_unwind(f).map_err(from_panic_payload)
+}
+
+/// Await a future, converting a panic into a [`StackError`] in the same way
+/// as [`catch_stack`].
+#[cfg(feature = "futures")]
+pub async fn catch_stack_async<F>(future: F) -> Result<F::Output, StackError>
+where
+    F: std::future::Future + UnwindSafe,
+{
+    use futures_util::FutureExt;
+
+    LAST_PANIC.with(|last| last.borrow_mut().take());
+    future.catch_unwind().await.map_err(from_panic_payload)
+}
+
+/// Build an error from a caught panic payload and the details recorded by
+/// the hook.
+pub(crate) fn from_panic_payload(payload: Box<dyn Any + Send>) -> StackError {
+    let details = LAST_PANIC.with(|last| last.borrow_mut().take());
+    panic_error(&*payload, details)
+}
+
+/// Build an error from the panic being handled by a hook, with its location
+/// and backtrace.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) fn from_panic_info(info: &std::panic::PanicHookInfo<'_>) -> StackError {
+    panic_error(info.payload(), Some(PanicDetails::capture(info)))
+}
+
+/// Build an error from a panic payload and the details of the panic. The
+/// location is added as the `location` field and the backtrace is attached.
+fn panic_error(payload: &(dyn Any + Send), details: Option<PanicDetails>) -> StackError {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    };
+    let (location, backtrace) = match details {
+        Some(details) => (details.location, details.backtrace),
+        None => (None, None),
+    };
+    let mut error = StackError::from_panic(format!("panicked: {message}"), backtrace);
+    if let Some(location) = location {
+        error = error.with_err_field("location", location);
+    }
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_catch_stack_passes_value() {
+        assert_eq!(catch_stack(|| 42).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_catch_stack_converts_panic() {
+        install_panic_hook();
+        let error = catch_stack(|| panic!("boom {}", 42)).unwrap_err();
+        assert_eq!(format!("{:?}", error), "panicked: boom 42");
+        assert_eq!(error.err_code(), Some(&ErrorCode::RuntimePanic));
+        assert!(error
+            .err_fields()
+            .iter()
+            .any(|(key, value)| key == "location" && value.starts_with("src/panic.rs")));
+        assert!(error.err_fields().iter().all(|(key, _)| key != "backtrace"));
+        assert!(error.err_location().is_none());
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_catch_stack_async_converts_panic() {
+        use futures_util::FutureExt;
+
+        let result = catch_stack_async(async {
+            panic!("boom");
+        })
+        .now_or_never()
+        .unwrap();
+        let error: StackError = result.unwrap_err();
+        assert_eq!(format!("{:?}", error), "panicked: boom");
+        assert_eq!(error.err_code(), Some(&ErrorCode::RuntimePanic));
+    }
+}
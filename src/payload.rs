@@ -0,0 +1,400 @@
+//! Provides the [`ErrorPayload`] type: the serialized form of a
+//! [`StackError`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::hint::RecoveryHint;
+use crate::origin::ErrorOrigin;
+use crate::redact::redact;
+use crate::severity::Severity;
+use crate::span::{ErrorPosition, ErrorSpan};
+
+/// Serialized form of a [`StackError`], with its frames ordered from the
+/// root to the top.
+///
+/// [`StackError`] implements `Serialize` and `Deserialize` through this type.
+/// Wrapped errors are serialized as their messages, so they can't be
+/// downcast after a round trip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ErrorPayload {
+    /// The frames of the error, from the root to the top.
+    pub frames: Vec<FramePayload>,
+}
+
+/// Serialized form of a single frame of a [`StackError`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FramePayload {
+    /// The redacted message of the frame, absent if it is empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// The error code. Unknown codes are dropped when deserializing.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_known"
+    )]
+    pub code: Option<ErrorCode>,
+    /// The URI documenting the error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    /// The key-value fields of the frame, with redacted values.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<(String, String)>,
+    /// The spans of source input the error points at.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<ErrorSpan>,
+    /// The position in source input at which the error occurred.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<ErrorPosition>,
+    /// How a caller could recover from the error.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_known"
+    )]
+    pub hint: Option<RecoveryHint>,
+    /// Whether the error was caused by the user, the service or an upstream
+    /// dependency.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_known"
+    )]
+    pub origin: Option<ErrorOrigin>,
+    /// The severity of the error.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_known"
+    )]
+    pub severity: Option<Severity>,
+    /// The ID of the trace in which the error was created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// The ID of the tracing span in which the error was created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span_id: Option<u64>,
+    /// The number of times the error occurred, absent if it occurred once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_count: Option<usize>,
+}
+
+/// Deserialize a code or other attribute enum, dropping values unknown to
+/// this version of the crate so that payloads from services using newer
+/// variants can still be decoded.
+fn deserialize_known<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Known<T> {
+        Known(T),
+        Unknown(serde::de::IgnoredAny),
+    }
+
+    Ok(match Option::<Known<T>>::deserialize(deserializer)? {
+        Some(Known::Known(value)) => Some(value),
+        Some(Known::Unknown(_)) | None => None,
+    })
+}
+
+impl From<&StackError> for ErrorPayload {
+    fn from(error: &StackError) -> Self {
+        let mut frames: Vec<_> = std::iter::successors(Some(error), |e| {
+            std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+        })
+        .map(|frame| {
+            let message = redact(&frame.frame_msg().to_string());
+            FramePayload {
+                message: (!message.is_empty()).then_some(message),
+                code: frame.err_code().copied(),
+                uri: frame.err_uri().map(str::to_string),
+                fields: frame
+                    .err_fields()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), redact(value)))
+                    .collect(),
+                spans: frame.err_spans().to_vec(),
+                position: frame.err_position().copied(),
+                hint: frame.err_hint().copied(),
+                origin: frame.err_origin().copied(),
+                severity: frame.err_severity().copied(),
+                trace_id: frame.err_trace_id().map(str::to_string),
+                span_id: frame.err_span_id(),
+                repeat_count: (frame.repeat_count() > 1).then_some(frame.repeat_count()),
+            }
+        })
+        .collect();
+        frames.reverse();
+        Self { frames }
+    }
+}
+
+impl From<ErrorPayload> for StackError {
+    fn from(payload: ErrorPayload) -> Self {
+        let mut error: Option<StackError> = None;
+        for frame in payload.frames {
+            let mut next = match (error, frame.message) {
+                (Some(error), Some(message)) => error.stack_err_msg(message),
+                (Some(error), None) => error.stack_err(),
+                (None, Some(message)) => StackError::from_msg(message),
+                (None, None) => StackError::empty(),
+            };
+            // Payload frames carry only their own attributes, and not those of
+            // the ambient context in which they are deserialized.
+            next = next
+                .with_no_err_code()
+                .with_no_err_uri()
+                .with_no_err_fields()
+                .with_no_err_hint()
+                .with_no_err_origin()
+                .with_no_err_severity()
+                .with_no_err_trace()
+                .with_no_err_location()
+                .with_no_err_capture();
+            if let Some(code) = frame.code {
+                next = next.with_err_code(code);
+            }
+            if let Some(uri) = frame.uri {
+                next = next.with_err_uri(uri);
+            }
+            for (key, value) in frame.fields {
+                next = next.with_err_field(key, value);
+            }
+            for span in frame.spans {
+                next = next.with_err_span(span.source_name, span.range);
+            }
+            if let Some(position) = frame.position {
+                next = next.with_err_position(position.offset, position.line, position.column);
+            }
+            if let Some(hint) = frame.hint {
+                next = next.with_err_hint(hint);
+            }
+            if let Some(origin) = frame.origin {
+                next = next.with_err_origin(origin);
+            }
+            if let Some(severity) = frame.severity {
+                next = next.with_err_severity(severity);
+            }
+            if let Some(trace_id) = frame.trace_id {
+                next = next.with_err_trace(trace_id, frame.span_id);
+            }
+            if let Some(count) = frame.repeat_count {
+                next = next.with_repeat_count(count);
+            }
+            error = Some(next);
+        }
+        error.unwrap_or_default()
+    }
+}
+
+impl StackError {
+    /// Rebuilds an error chain received from another service.
+    ///
+    /// Frames keep the codes and URIs set by the originating service, and
+    /// frames stacked locally inherit them as usual, so that the original
+    /// classification is preserved end-to-end.
+    pub fn from_remote(payload: ErrorPayload) -> Self {
+        payload.into()
+    }
+}
+
+impl Serialize for StackError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorPayload::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StackError {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ErrorPayload::deserialize(deserializer).map(StackError::from)
+    }
+}
+
+/// Allows hand-written `Serialize` implementations and serializers to report
+/// errors as [`StackError`], with the `IoInvalidData` code.
+impl serde::ser::Error for StackError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        StackError::from_msg(msg.to_string()).with_err_code(ErrorCode::IoInvalidData)
+    }
+}
+
+/// Allows hand-written `Deserialize` implementations and deserializers to
+/// report errors as [`StackError`], with the `IoInvalidData` code.
+impl serde::de::Error for StackError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        StackError::from_msg(msg.to_string()).with_err_code(ErrorCode::IoInvalidData)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for StackError {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        ErrorPayload::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        ErrorPayload::json_schema(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_is_serde_error() {
+        let error = <StackError as serde::de::Error>::missing_field("id");
+        assert_eq!(format!("{:?}", error), "missing field `id`");
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoInvalidData));
+        let error = <StackError as serde::ser::Error>::custom("unsupported value");
+        assert_eq!(format!("{:?}", error), "unsupported value");
+    }
+
+    #[test]
+    fn test_error_round_trips() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .with_err_span("config.toml", 0..4)
+            .with_err_position(0, 1, 1)
+            .stack_err_msg("Stacked error")
+            .with_err_uri("https://example.com/stacked".to_string());
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"frames":[{"message":"Base error","code":"IoNotFound","#,
+                r#""fields":[["path","config.toml"]],"#,
+                r#""spans":[{"source_name":"config.toml","range":{"start":0,"end":4}}],"#,
+                r#""position":{"offset":0,"line":1,"column":1}},"#,
+                r#"{"message":"Stacked error","code":"IoNotFound","uri":"https://example.com/stacked"}]}"#
+            )
+        );
+        let decoded: StackError = serde_json::from_str(&json).unwrap();
+        assert_eq!(ErrorPayload::from(&decoded), ErrorPayload::from(&error));
+        assert!(decoded.err_location().is_none());
+        assert!(decoded.into_frames()[0].err_location().is_none());
+    }
+
+    #[test]
+    fn test_repeats_round_trip() {
+        let error = StackError::from_msg("Base error")
+            .stack_err_msg("Retry failed")
+            .stack_or_repeat_err_msg("Retry failed")
+            .stack_err_msg("Stacked error")
+            .stack_err_msg("Stacked error");
+        let json = serde_json::to_string(&error).unwrap();
+        let decoded: StackError = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            format!("{:?}", decoded),
+            "Base error\nRetry failed (x2)\nStacked error\nStacked error"
+        );
+    }
+
+    #[test]
+    fn test_remote_error_stacks() {
+        let remote = StackError::from_msg("Remote error")
+            .with_err_code(ErrorCode::HttpTooManyRequests)
+            .with_err_uri("https://remote.example.com/rate-limit".to_string());
+        let payload: ErrorPayload =
+            serde_json::from_str(&serde_json::to_string(&remote).unwrap()).unwrap();
+        let error = StackError::from_remote(payload).stack_err_msg("Local error");
+        assert_eq!(format!("{:?}", error), "Remote error\nLocal error");
+        assert_eq!(error.err_code(), Some(&ErrorCode::HttpTooManyRequests));
+        assert_eq!(
+            error.err_uri(),
+            Some("https://remote.example.com/rate-limit")
+        );
+    }
+
+    #[test]
+    fn test_hint_round_trips() {
+        let error = StackError::from_msg("Base error")
+            .with_err_hint(RecoveryHint::RetryAfter(std::time::Duration::from_secs(30)))
+            .with_err_origin(ErrorOrigin::Upstream)
+            .with_err_severity(Severity::Warn)
+            .stack_err_msg("Stacked error");
+        let json = serde_json::to_string(&error).unwrap();
+        assert!(json.contains(concat!(
+            r#""hint":{"RetryAfter":{"secs":30,"nanos":0}},"#,
+            r#""origin":"Upstream","severity":"Warn""#
+        )));
+        let decoded: StackError = serde_json::from_str(&json).unwrap();
+        assert_eq!(ErrorPayload::from(&decoded), ErrorPayload::from(&error));
+    }
+
+    #[test]
+    fn test_trace_round_trips() {
+        let error = StackError::from_msg("Base error")
+            .with_err_trace("4bf92f3577b34da6", Some(7))
+            .stack_err_msg("Stacked error");
+        let json = serde_json::to_string(&error).unwrap();
+        assert!(json.ends_with(r#""trace_id":"4bf92f3577b34da6","span_id":7}]}"#));
+        let decoded: StackError = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.err_trace_id(), Some("4bf92f3577b34da6"));
+        assert_eq!(ErrorPayload::from(&decoded), ErrorPayload::from(&error));
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[test]
+    fn test_decoded_frames_have_no_local_capture() {
+        let error = StackError::from_msg("Base error").stack_err_msg("Stacked error");
+        let json = serde_json::to_string(&error).unwrap();
+        let decoded: StackError = serde_json::from_str(&json).unwrap();
+        assert!(decoded.err_backtrace().is_none());
+        assert!(decoded
+            .into_frames()
+            .iter()
+            .all(|f| f.timestamp().is_none()));
+
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let error = StackError::from_panic("panicked".to_string(), Some(backtrace));
+        assert!(error.err_backtrace().is_some());
+        assert!(error.with_no_err_capture().err_backtrace().is_none());
+    }
+
+    #[test]
+    fn test_unknown_code_is_dropped() {
+        let json = r#"{"frames":[{"message":"Base error","code":"FutureCode","extra":1}]}"#;
+        let decoded: StackError = serde_json::from_str(json).unwrap();
+        assert_eq!(format!("{:?}", decoded), "Base error");
+        assert_eq!(decoded.err_code(), None);
+    }
+
+    #[test]
+    fn test_unknown_attributes_are_dropped() {
+        let json = r#"{"frames":[{"message":"Base error","hint":"FutureHint","origin":{"Future":1},"severity":"Critical"}]}"#;
+        let decoded: StackError = serde_json::from_str(json).unwrap();
+        assert_eq!(format!("{:?}", decoded), "Base error");
+        assert_eq!(decoded.err_hint(), None);
+        assert_eq!(decoded.err_origin(), None);
+        assert_eq!(decoded.err_severity(), None);
+
+        let json =
+            r#"{"frames":[{"message":"Base error","hint":{"RetryAfter":{"secs":1,"nanos":0}}}]}"#;
+        let decoded: StackError = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            decoded.err_hint(),
+            Some(&RecoveryHint::RetryAfter(std::time::Duration::from_secs(1)))
+        );
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_payload_has_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(StackError)).unwrap();
+        assert_eq!(schema["title"], "ErrorPayload");
+        assert!(schema["$defs"]["ErrorCode"].is_object());
+        assert_eq!(
+            schema["$defs"]["FramePayload"]["properties"]["uri"]["description"],
+            "The URI documenting the error."
+        );
+    }
+}
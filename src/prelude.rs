@@ -1,7 +1,43 @@
 //! Provides re-exports for commonly used types and traits, and defines the
 //! [`StackResult`] type.
+//!
+//! The flat prelude exports everything. The [`base`], [`codes`] and [`ext`]
+//! modules can be imported instead to pull in only part of it.
 
-pub use crate::codes::ErrorCode;
-pub use crate::error::{ErrorStacks, StackError};
+pub use self::base::*;
+pub use self::codes::*;
+pub use self::ext::*;
+pub use crate::builder::StackErrorBuilder;
+pub use crate::context::push_err_context;
+#[cfg(feature = "tokio")]
+pub use crate::context::with_error_context;
+pub use crate::error_uri;
 pub use crate::fmt_loc;
-pub type StackResult<T> = std::result::Result<T, StackError>;
+pub use crate::hint::RecoveryHint;
+pub use crate::origin::ErrorOrigin;
+pub use crate::severity::Severity;
+pub use crate::span::{ErrorPosition, ErrorSpan};
+pub use crate::uri::ErrorUriScheme;
+
+/// The core error type and trait, and the [`StackResult`] type.
+pub mod base {
+    pub use crate::dynamic::{BoxStackError, DynErrorStacks};
+    pub use crate::error::{ErrorStacks, StackError};
+    pub type StackResult<T, E = StackError> = std::result::Result<T, E>;
+}
+
+/// The error codes.
+pub mod codes {
+    pub use crate::codes::ErrorCode;
+}
+
+/// The extension traits for results, futures, streams and commands.
+pub mod ext {
+    #[cfg(feature = "futures")]
+    pub use crate::future::StackFutureExt;
+    #[cfg(feature = "io")]
+    pub use crate::process::StackCommandExt;
+    pub use crate::result::StackResultExt;
+    #[cfg(feature = "futures")]
+    pub use crate::stream::StackStreamExt;
+}
@@ -0,0 +1,112 @@
+//! Provides conversions and helpers for `std::process` results.
+
+use std::process::{Command, ExitStatus, Output};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+impl StackError {
+    /// Creates an error with the [`ErrorCode::RuntimeProcessFailed`] code for
+    /// an unsuccessful exit status, or returns `None` if the process
+    /// succeeded. The exit code, or the signal on Unix, is added as the
+    /// `exit_code` or `signal` field.
+    #[track_caller]
+    pub fn from_exit_status(status: ExitStatus) -> Option<Self> {
+        if status.success() {
+            return None;
+        }
+        let mut err = StackError::from_msg(format!("process failed with {status}"))
+            .with_err_code(ErrorCode::RuntimeProcessFailed);
+        if let Some(code) = status.code() {
+            err = err.with_err_field("exit_code", code);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                err = err.with_err_field("signal", signal);
+            }
+        }
+        Some(err)
+    }
+}
+
+/// Extension trait for running a [`Command`] and getting a [`StackError`]
+/// if it can't be spawned or exits unsuccessfully.
+///
+/// The program name is added as the `program` field in both cases.
+pub trait StackCommandExt {
+    /// Run the command and wait for it to finish, like [`Command::status`].
+    fn stack_status(&mut self) -> Result<ExitStatus, StackError>;
+    /// Run the command and collect its output, like [`Command::output`].
+    /// The captured stderr is added as the `stderr` field on failure.
+    fn stack_output(&mut self) -> Result<Output, StackError>;
+}
+
+impl StackCommandExt for Command {
+    fn stack_status(&mut self) -> Result<ExitStatus, StackError> {
+        let program = self.get_program().to_string_lossy().into_owned();
+        let status = self
+            .status()
+            .map_err(StackError::from)
+            .with_err_field("program", &program)?;
+        match StackError::from_exit_status(status) {
+            Some(err) => Err(err.with_err_field("program", program)),
+            None => Ok(status),
+        }
+    }
+
+    fn stack_output(&mut self) -> Result<Output, StackError> {
+        let program = self.get_program().to_string_lossy().into_owned();
+        let output = self
+            .output()
+            .map_err(StackError::from)
+            .with_err_field("program", &program)?;
+        match StackError::from_exit_status(output.status) {
+            Some(err) => Err(err
+                .with_err_field("program", program)
+                .with_err_field("stderr", String::from_utf8_lossy(&output.stderr))),
+            None => Ok(output),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_failure_is_coded() {
+        let err = Command::new("stackerror-missing-program")
+            .stack_status()
+            .unwrap_err();
+        assert_eq!(err.err_code(), Some(&ErrorCode::IoNotFound));
+        assert_eq!(
+            err.err_fields(),
+            &[(
+                "program".to_string(),
+                "stackerror-missing-program".to_string()
+            )]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_status_is_coded() {
+        let err = Command::new("sh")
+            .args(["-c", "echo oops >&2; exit 3"])
+            .stack_output()
+            .unwrap_err();
+        assert_eq!(err.err_code(), Some(&ErrorCode::RuntimeProcessFailed));
+        assert_eq!(
+            err.err_fields(),
+            &[
+                ("exit_code".to_string(), "3".to_string()),
+                ("program".to_string(), "sh".to_string()),
+                ("stderr".to_string(), "oops\n".to_string())
+            ]
+        );
+        let status = Command::new("true").stack_status().unwrap();
+        assert!(StackError::from_exit_status(status).is_none());
+    }
+}
@@ -0,0 +1,459 @@
+//! Provides a protobuf representation of errors, and conversions to and from
+//! `google.rpc.Status`.
+//!
+//! The error chain is carried in the status details as an [`ErrorChain`]
+//! message, so a [`StackError`] can be rebuilt with its codes and URIs on the
+//! receiving side. Statuses without these details are converted to a single
+//! frame with the status message.
+
+use std::time::Duration;
+
+use prost::Message;
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::hint::RecoveryHint;
+use crate::origin::ErrorOrigin;
+use crate::redact::redact;
+use crate::severity::Severity;
+
+/// The type URL of [`ErrorChain`] details.
+pub const ERROR_CHAIN_TYPE_URL: &str = "type.googleapis.com/stackerror.ErrorChain";
+
+/// Protobuf message for an error chain, with frames ordered from the root to
+/// the top.
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorChain {
+    /// The frames of the error, from the root to the top.
+    #[prost(message, repeated, tag = "1")]
+    pub frames: Vec<ErrorFrame>,
+}
+
+/// Protobuf message for a single frame.
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorFrame {
+    /// The redacted message of the frame, absent if it is empty.
+    #[prost(string, optional, tag = "1")]
+    pub message: Option<String>,
+    /// The [`ErrorCode`] variant name.
+    #[prost(string, optional, tag = "2")]
+    pub code: Option<String>,
+    /// The URI documenting the error.
+    #[prost(string, optional, tag = "3")]
+    pub uri: Option<String>,
+    /// The key-value fields of the frame, with redacted values.
+    #[prost(message, repeated, tag = "4")]
+    pub fields: Vec<ErrorField>,
+    /// The number of times the error occurred, absent if it occurred once.
+    #[prost(uint32, optional, tag = "5")]
+    pub repeat_count: Option<u32>,
+    /// The ID of the trace in which the error was created.
+    #[prost(string, optional, tag = "6")]
+    pub trace_id: Option<String>,
+    /// The ID of the tracing span in which the error was created.
+    #[prost(uint64, optional, tag = "7")]
+    pub span_id: Option<u64>,
+    /// The [`RecoveryHint`] variant name.
+    #[prost(string, optional, tag = "8")]
+    pub hint: Option<String>,
+    /// The delay of a [`RecoveryHint::RetryAfter`] hint.
+    #[prost(message, optional, tag = "9")]
+    pub retry_after: Option<prost_types::Duration>,
+    /// The [`ErrorOrigin`] variant name.
+    #[prost(string, optional, tag = "10")]
+    pub origin: Option<String>,
+    /// The [`Severity`] variant name.
+    #[prost(string, optional, tag = "11")]
+    pub severity: Option<String>,
+    /// The spans of source input the error points at.
+    #[prost(message, repeated, tag = "12")]
+    pub spans: Vec<ErrorSpan>,
+    /// The position in source input at which the error occurred.
+    #[prost(message, optional, tag = "13")]
+    pub position: Option<ErrorPosition>,
+}
+
+/// Protobuf message for a key-value field.
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorField {
+    /// The key of the field.
+    #[prost(string, tag = "1")]
+    pub key: String,
+    /// The value of the field.
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+/// Protobuf message for a span of source input, with a half-open byte range.
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorSpan {
+    /// The name of the source, e.g. a file path.
+    #[prost(string, tag = "1")]
+    pub source_name: String,
+    /// The byte offset of the start of the span.
+    #[prost(uint64, tag = "2")]
+    pub start: u64,
+    /// The byte offset of the end of the span, excluded.
+    #[prost(uint64, tag = "3")]
+    pub end: u64,
+}
+
+/// Protobuf message for a position in source input.
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorPosition {
+    /// The byte offset in the source.
+    #[prost(uint64, tag = "1")]
+    pub offset: u64,
+    /// The 1-based line number.
+    #[prost(uint64, tag = "2")]
+    pub line: u64,
+    /// The 1-based column number.
+    #[prost(uint64, tag = "3")]
+    pub column: u64,
+}
+
+/// Protobuf message for `google.rpc.Status`.
+#[derive(Clone, PartialEq, Message)]
+pub struct RpcStatus {
+    /// The `google.rpc.Code` value.
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    /// The redacted message of the top frame.
+    #[prost(string, tag = "2")]
+    pub message: String,
+    /// The status details, carrying an [`ErrorChain`].
+    #[prost(message, repeated, tag = "3")]
+    pub details: Vec<prost_types::Any>,
+}
+
+/// Get the `google.rpc.Code` value for an error code.
+pub fn grpc_code(code: Option<ErrorCode>) -> i32 {
+    let Some(code) = code else {
+        // UNKNOWN
+        return 2;
+    };
+    match code {
+        // INVALID_ARGUMENT
+        ErrorCode::RuntimeInvalidValue
+        | ErrorCode::RuntimeInvalidIndex
+        | ErrorCode::RuntimeInvalidKey
+        | ErrorCode::HttpBadRequest
+        | ErrorCode::HttpUnprocessableEntity
+        | ErrorCode::IoInvalidInput
+        | ErrorCode::IoInvalidData => 3,
+        // DEADLINE_EXCEEDED
+        ErrorCode::HttpRequestTimeout | ErrorCode::HttpGatewayTimeout | ErrorCode::IoTimedOut => 4,
+        // NOT_FOUND
+        ErrorCode::HttpNotFound | ErrorCode::HttpGone | ErrorCode::IoNotFound => 5,
+        // ALREADY_EXISTS
+        ErrorCode::HttpConflict | ErrorCode::IoAlreadyExists => 6,
+        // PERMISSION_DENIED
+        ErrorCode::HttpForbidden | ErrorCode::IoPermissionDenied => 7,
+        // RESOURCE_EXHAUSTED
+        ErrorCode::HttpTooManyRequests | ErrorCode::IoOutOfMemory => 8,
+        // FAILED_PRECONDITION
+        ErrorCode::HttpPreconditionFailed | ErrorCode::HttpPreconditionRequired => 9,
+        // ABORTED
+        ErrorCode::IoInterrupted => 10,
+        // OUT_OF_RANGE
+        ErrorCode::HttpRangeNotSatisfiable => 11,
+        // UNIMPLEMENTED
+        ErrorCode::RuntimeNotImplemented
+        | ErrorCode::HttpNotImplemented
+        | ErrorCode::IoUnsupported => 12,
+        // INTERNAL
+        ErrorCode::RuntimePanic | ErrorCode::HttpInternalServerError => 13,
+        // UNAVAILABLE
+        ErrorCode::HttpServiceUnavailable
+        | ErrorCode::HttpBadGateway
+        | ErrorCode::IoConnectionRefused
+        | ErrorCode::IoConnectionReset
+        | ErrorCode::IoConnectionAborted
+        | ErrorCode::IoNotConnected
+        | ErrorCode::IoBrokenPipe => 14,
+        // UNAUTHENTICATED
+        ErrorCode::HttpUnauthorized => 16,
+        // UNKNOWN
+        _ => 2,
+    }
+}
+
+fn parse_hint(name: &str, retry_after: Option<prost_types::Duration>) -> Option<RecoveryHint> {
+    match name {
+        "Retry" => Some(RecoveryHint::Retry),
+        "RetryAfter" => {
+            let delay = retry_after?;
+            let secs = u64::try_from(delay.seconds).ok()?;
+            let nanos = u32::try_from(delay.nanos).ok()?;
+            Some(RecoveryHint::RetryAfter(Duration::new(secs, nanos)))
+        }
+        "Reauthenticate" => Some(RecoveryHint::Reauthenticate),
+        "UseFallbackResource" => Some(RecoveryHint::UseFallbackResource),
+        "GiveUp" => Some(RecoveryHint::GiveUp),
+        _ => None,
+    }
+}
+
+fn parse_origin(name: &str) -> Option<ErrorOrigin> {
+    match name {
+        "User" => Some(ErrorOrigin::User),
+        "Internal" => Some(ErrorOrigin::Internal),
+        "Upstream" => Some(ErrorOrigin::Upstream),
+        _ => None,
+    }
+}
+
+fn parse_severity(name: &str) -> Option<Severity> {
+    match name {
+        "Debug" => Some(Severity::Debug),
+        "Info" => Some(Severity::Info),
+        "Warn" => Some(Severity::Warn),
+        "Error" => Some(Severity::Error),
+        "Fatal" => Some(Severity::Fatal),
+        _ => None,
+    }
+}
+
+impl From<&StackError> for ErrorChain {
+    fn from(error: &StackError) -> Self {
+        let mut frames: Vec<_> = std::iter::successors(Some(error), |e| {
+            std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+        })
+        .map(|frame| {
+            let message = redact(&frame.frame_msg().to_string());
+            let retry_after = match frame.err_hint() {
+                Some(RecoveryHint::RetryAfter(delay)) => Some(prost_types::Duration {
+                    seconds: i64::try_from(delay.as_secs()).unwrap_or(i64::MAX),
+                    nanos: delay.subsec_nanos() as i32,
+                }),
+                _ => None,
+            };
+            ErrorFrame {
+                message: (!message.is_empty()).then_some(message),
+                code: frame.err_code().map(|code| format!("{code:?}")),
+                uri: frame.err_uri().map(str::to_string),
+                fields: frame
+                    .err_fields()
+                    .iter()
+                    .map(|(key, value)| ErrorField {
+                        key: key.clone(),
+                        value: redact(value),
+                    })
+                    .collect(),
+                repeat_count: (frame.repeat_count() > 1)
+                    .then(|| u32::try_from(frame.repeat_count()).unwrap_or(u32::MAX)),
+                trace_id: frame.err_trace_id().map(str::to_string),
+                span_id: frame.err_span_id(),
+                hint: frame.err_hint().map(|hint| match hint {
+                    RecoveryHint::RetryAfter(_) => "RetryAfter".to_string(),
+                    hint => format!("{hint:?}"),
+                }),
+                retry_after,
+                origin: frame.err_origin().map(|origin| format!("{origin:?}")),
+                severity: frame.err_severity().map(|severity| format!("{severity:?}")),
+                spans: frame
+                    .err_spans()
+                    .iter()
+                    .map(|span| ErrorSpan {
+                        source_name: span.source_name.clone(),
+                        start: span.range.start as u64,
+                        end: span.range.end as u64,
+                    })
+                    .collect(),
+                position: frame.err_position().map(|position| ErrorPosition {
+                    offset: position.offset as u64,
+                    line: position.line as u64,
+                    column: position.column as u64,
+                }),
+            }
+        })
+        .collect();
+        frames.reverse();
+        Self { frames }
+    }
+}
+
+/// Unknown code, hint, origin and severity names are dropped, so that chains
+/// from services using newer variants can still be decoded.
+impl From<ErrorChain> for StackError {
+    fn from(chain: ErrorChain) -> Self {
+        let mut error: Option<StackError> = None;
+        for frame in chain.frames {
+            let mut next = match (error, frame.message) {
+                (Some(error), Some(message)) => error.stack_err_msg(message),
+                (Some(error), None) => error.stack_err(),
+                (None, Some(message)) => StackError::from_msg(message),
+                (None, None) => StackError::empty(),
+            };
+            next = next
+                .with_no_err_code()
+                .with_no_err_uri()
+                .with_no_err_fields()
+                .with_no_err_hint()
+                .with_no_err_origin()
+                .with_no_err_severity()
+                .with_no_err_trace()
+                .with_no_err_location()
+                .with_no_err_capture();
+            if let Some(code) = frame.code.and_then(|name| name.parse().ok()) {
+                next = next.with_err_code(code);
+            }
+            if let Some(uri) = frame.uri {
+                next = next.with_err_uri(uri);
+            }
+            for field in frame.fields {
+                next = next.with_err_field(field.key, field.value);
+            }
+            for span in frame.spans {
+                next = next.with_err_span(span.source_name, span.start as usize..span.end as usize);
+            }
+            if let Some(position) = frame.position {
+                next = next.with_err_position(
+                    position.offset as usize,
+                    position.line as usize,
+                    position.column as usize,
+                );
+            }
+            if let Some(hint) = frame
+                .hint
+                .and_then(|name| parse_hint(&name, frame.retry_after))
+            {
+                next = next.with_err_hint(hint);
+            }
+            if let Some(origin) = frame.origin.and_then(|name| parse_origin(&name)) {
+                next = next.with_err_origin(origin);
+            }
+            if let Some(severity) = frame.severity.and_then(|name| parse_severity(&name)) {
+                next = next.with_err_severity(severity);
+            }
+            if let Some(trace_id) = frame.trace_id {
+                next = next.with_err_trace(trace_id, frame.span_id);
+            }
+            if let Some(count) = frame.repeat_count {
+                next = next.with_repeat_count(count as usize);
+            }
+            error = Some(next);
+        }
+        error.unwrap_or_default()
+    }
+}
+
+impl From<&StackError> for RpcStatus {
+    fn from(error: &StackError) -> Self {
+        Self {
+            code: grpc_code(error.err_code().copied()),
+            message: redact(&error.frame_msg().to_string()),
+            details: vec![prost_types::Any {
+                type_url: ERROR_CHAIN_TYPE_URL.to_string(),
+                value: ErrorChain::from(error).encode_to_vec(),
+            }],
+        }
+    }
+}
+
+/// The `grpc_code` field is set if the status doesn't carry an
+/// [`ErrorChain`].
+impl From<RpcStatus> for StackError {
+    fn from(status: RpcStatus) -> Self {
+        let chain = status
+            .details
+            .iter()
+            .filter(|any| any.type_url == ERROR_CHAIN_TYPE_URL)
+            .find_map(|any| ErrorChain::decode(any.value.as_slice()).ok());
+        match chain {
+            Some(chain) => chain.into(),
+            None => StackError::from_msg(status.message)
+                .with_no_err_location()
+                .with_no_err_capture()
+                .with_err_field("grpc_code", status.code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_round_trips() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .stack_err_msg("Stacked error")
+            .with_err_uri("https://example.com/stacked".to_string());
+        let status = RpcStatus::from(&error);
+        assert_eq!(status.code, 5);
+        assert_eq!(status.message, "Stacked error");
+        let bytes = status.encode_to_vec();
+        let decoded: StackError = RpcStatus::decode(bytes.as_slice()).unwrap().into();
+        assert_eq!(format!("{:?}", decoded), "Base error\nStacked error");
+        assert_eq!(decoded.err_code(), Some(&ErrorCode::IoNotFound));
+        assert_eq!(decoded.err_uri(), Some("https://example.com/stacked"));
+        assert_eq!(ErrorChain::from(&decoded), ErrorChain::from(&error));
+        assert!(decoded.err_location().is_none());
+        assert!(decoded.timestamp().is_none());
+    }
+
+    #[test]
+    fn test_status_round_trips_attributes() {
+        let error = StackError::from_msg("Invalid token")
+            .with_err_span("query.sql", 7..12)
+            .with_err_position(7, 1, 8)
+            .with_err_severity(Severity::Warn)
+            .stack_err_msg("Request failed")
+            .with_err_hint(RecoveryHint::RetryAfter(Duration::from_millis(1500)))
+            .with_err_origin(ErrorOrigin::Upstream)
+            .with_err_trace("4bf92f3577b34da6a3ce929d0e0e4736", Some(42));
+        let bytes = RpcStatus::from(&error).encode_to_vec();
+        let decoded: StackError = RpcStatus::decode(bytes.as_slice()).unwrap().into();
+        assert_eq!(ErrorChain::from(&decoded), ErrorChain::from(&error));
+        assert_eq!(
+            decoded.err_hint(),
+            Some(&RecoveryHint::RetryAfter(Duration::from_millis(1500)))
+        );
+        assert_eq!(decoded.err_origin(), Some(&ErrorOrigin::Upstream));
+        assert_eq!(
+            decoded.err_trace_id(),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+        assert_eq!(decoded.err_span_id(), Some(42));
+        let root = std::error::Error::source(&decoded)
+            .and_then(|s| s.downcast_ref::<StackError>())
+            .unwrap();
+        assert_eq!(root.err_spans()[0].range, 7..12);
+        assert_eq!(root.err_position().map(|p| p.column), Some(8));
+        assert_eq!(root.err_severity(), Some(&Severity::Warn));
+    }
+
+    #[test]
+    fn test_unknown_names_are_dropped() {
+        let chain = ErrorChain {
+            frames: vec![ErrorFrame {
+                message: Some("Failed".to_string()),
+                hint: Some("RetryTomorrow".to_string()),
+                origin: Some("Cosmic".to_string()),
+                severity: Some("Catastrophic".to_string()),
+                ..Default::default()
+            }],
+        };
+        let error: StackError = chain.into();
+        assert!(error.err_hint().is_none());
+        assert!(error.err_origin().is_none());
+        assert!(error.err_severity().is_none());
+    }
+
+    #[test]
+    fn test_status_without_chain() {
+        let status = RpcStatus {
+            code: 14,
+            message: "unavailable".to_string(),
+            details: Vec::new(),
+        };
+        let error: StackError = status.into();
+        assert_eq!(format!("{:?}", error), "unavailable");
+        assert_eq!(
+            error.err_fields(),
+            &[("grpc_code".to_string(), "14".to_string())]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Provides a global redactor applied to errors leaving the process.
+//!
+//! Messages and field values are passed through the redactor when errors are
+//! converted to their serialized forms, such as
+//! [`ErrorPayload`](crate::payload::ErrorPayload) or the protobuf messages,
+//! so that secrets accidentally formatted into errors are not sent to
+//! clients or other services. Errors rendered locally, e.g. with `Debug`, are
+//! not redacted.
+
+use std::sync::{Arc, RwLock};
+
+type Redactor = dyn Fn(&str) -> String + Send + Sync;
+
+static REDACTOR: RwLock<Option<Arc<Redactor>>> = RwLock::new(None);
+
+/// Set the global redactor, replacing any previous one.
+///
+/// ```rust
+/// set_redactor(|text| text.replace(&api_key, "[REDACTED]"));
+/// ```
+pub fn set_redactor(redactor: impl Fn(&str) -> String + Send + Sync + 'static) {
+    *REDACTOR.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(redactor));
+}
+
+/// Remove the global redactor.
+pub fn clear_redactor() {
+    *REDACTOR.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Apply the global redactor to the text. The text is returned unchanged if
+/// no redactor is set, or if redaction is disabled in the
+/// [`StackErrorConfig`](crate::config::StackErrorConfig).
+pub fn redact(text: &str) -> String {
+    if !crate::config::config().redact {
+        return text.to_string();
+    }
+    // The lock is released before calling the redactor, so that it can
+    // itself create errors
+    let redactor = REDACTOR.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match redactor {
+        Some(redactor) => redactor(text),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redactor_applies_to_payloads() {
+        set_redactor(|text| text.replace("hunter2", "[REDACTED]"));
+        assert_eq!(redact("password hunter2"), "password [REDACTED]");
+        #[cfg(feature = "serde")]
+        {
+            use crate::error::{ErrorStacks, StackError};
+            use crate::payload::ErrorPayload;
+
+            let error = StackError::from_msg("login failed for hunter2")
+                .with_err_field("password", "hunter2");
+            let frame = &ErrorPayload::from(&error).frames[0];
+            assert_eq!(
+                frame.message.as_deref(),
+                Some("login failed for [REDACTED]")
+            );
+            assert_eq!(
+                frame.fields,
+                [("password".to_string(), "[REDACTED]".to_string())]
+            );
+        }
+        #[cfg(feature = "juniper")]
+        {
+            use crate::error::{ErrorStacks, StackError};
+            use ::juniper::{graphql_value, DefaultScalarValue, FieldError, IntoFieldError};
+
+            let error = StackError::from_msg("login failed for hunter2")
+                .with_err_uri("https://example.com/login?password=hunter2".to_string());
+            let field_error: FieldError<DefaultScalarValue> = error.into_field_error();
+            assert_eq!(field_error.message(), "login failed for [REDACTED]");
+            assert_eq!(
+                field_error.extensions(),
+                &graphql_value!({ "uri": "https://example.com/login?password=[REDACTED]" })
+            );
+        }
+        #[cfg(feature = "html")]
+        {
+            use crate::error::{ErrorStacks, StackError};
+            use crate::html::HtmlRenderer;
+
+            let error = StackError::from_msg("login failed")
+                .with_err_uri("https://example.com/login?password=hunter2".to_string());
+            let page = HtmlRenderer::new().with_details(true).render(&error);
+            assert!(page.contains("password=[REDACTED]"));
+            assert!(!page.contains("hunter2"));
+        }
+        clear_redactor();
+        assert_eq!(redact("password hunter2"), "password hunter2");
+    }
+}
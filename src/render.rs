@@ -0,0 +1,546 @@
+//! Provides the rendering of errors for `Display` and `Debug` output and
+//! reports, and the [`ErrorRenderer`] trait to replace it.
+//!
+//! The verbosity is set by the [`StackErrorConfig`], which is read from the
+//! `STACKERROR_VERBOSITY` environment variable unless it was set at startup,
+//! so operators can get more detail from a deployed binary without
+//! recompiling. It can be one of:
+//!
+//! - `terse`: the top message only,
+//! - `chain`: the messages of all frames, from the root to the top (default),
+//! - `full`: the chain followed by the code, URI and the source locations,
+//!   positions and fields of all frames, the backtrace if one was captured,
+//!   and with the `span-trace` feature, the trace of the `tracing` spans.
+//!
+//! At the other verbosities, the alternate `Debug` output (`{:#?}`) is also
+//! followed by the backtrace, if one was captured.
+//!
+//! Errors [attached](StackError::attach) to a frame, such as the individual
+//! failures of a batch, are rendered below it as the branches of a tree.
+//!
+//! [`StackErrorConfig`]: crate::config::StackErrorConfig
+
+use std::sync::{Arc, RwLock};
+
+use crate::error::{ErrorStacks, StackError};
+
+/// The amount of detail with which errors are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Verbosity {
+    /// The top message only.
+    Terse,
+    /// The messages of all frames, from the root to the top.
+    #[default]
+    Chain,
+    /// The chain followed by the code, URI, locations, fields and backtrace.
+    Full,
+}
+
+impl std::str::FromStr for Verbosity {
+    type Err = UnknownVerbosity;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "terse" => Ok(Self::Terse),
+            "chain" => Ok(Self::Chain),
+            "full" => Ok(Self::Full),
+            _ => Err(UnknownVerbosity),
+        }
+    }
+}
+
+/// Error returned when parsing an unknown [`Verbosity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownVerbosity;
+
+impl std::fmt::Display for UnknownVerbosity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown verbosity")
+    }
+}
+
+impl std::error::Error for UnknownVerbosity {}
+
+/// Get the verbosity of the process-wide
+/// [`StackErrorConfig`](crate::config::StackErrorConfig).
+pub fn verbosity() -> Verbosity {
+    crate::config::config().verbosity
+}
+
+impl<C> StackError<C>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+{
+    /// Render the error with the given verbosity. `Debug` renders with the
+    /// verbosity returned by [`verbosity`].
+    pub fn render(&self, verbosity: Verbosity) -> String {
+        struct Render<'a, C>(&'a StackError<C>, Verbosity);
+
+        impl<C> std::fmt::Display for Render<'_, C>
+        where
+            C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+        {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                fmt_error(self.0, self.1, f)
+            }
+        }
+
+        Render(self, verbosity).to_string()
+    }
+}
+
+impl StackError {
+    /// Render the frames as a numbered report, from the root to the top,
+    /// wrapped to the width of the terminal, see [`terminal_width`]. Messages
+    /// aren't wrapped if stderr isn't a terminal. See
+    /// [`StackError::render_report_width`].
+    pub fn render_report(&self) -> String {
+        self.render_report_width(terminal_width().unwrap_or(usize::MAX))
+    }
+
+    /// Render the frames as a numbered report, from the root to the top.
+    /// By default, messages are wrapped at word boundaries to the given
+    /// width, with a hanging indent so that continuation lines align with the
+    /// message.
+    pub fn render_report_width(&self, width: usize) -> String {
+        renderer().report(self, width)
+    }
+
+    /// Render the frames from the root to the top, each followed by an
+    /// indented block with its code, URI, position, source location, hint,
+    /// origin, severity, elapsed time, fields and creation time, followed by
+    /// the span trace with the `span-trace` feature and the backtrace if one
+    /// was captured. This is meant for log files and bug reports, where more
+    /// detail than the `Debug` output is wanted.
+    pub fn render_verbose(&self) -> String {
+        let mut frames: Vec<_> = std::iter::successors(Some(self), |e| {
+            std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+        })
+        .collect();
+        frames.reverse();
+        let mut out = String::new();
+        for (idx, frame) in frames.iter().enumerate() {
+            out += &format!("{idx}: {}", frame.frame_msg());
+            if frame.repeat_count() > 1 {
+                out += &format!(" (x{})", frame.repeat_count());
+            }
+            out += "\n";
+            if let Some(code) = frame.err_code() {
+                out += &format!("   code: {code:?}\n");
+            }
+            if let Some(uri) = frame.err_uri() {
+                out += &format!("   uri: {uri}\n");
+            }
+            if let Some(position) = frame.err_position() {
+                out += &format!("   position: {position}\n");
+            }
+            if let Some(location) = frame.err_location() {
+                out += &format!("   location: {location}\n");
+            }
+            if let Some(hint) = frame.err_hint() {
+                out += &format!("   hint: {hint:?}\n");
+            }
+            if let Some(origin) = frame.err_origin() {
+                out += &format!("   origin: {origin:?}\n");
+            }
+            if let Some(severity) = frame.err_severity() {
+                out += &format!("   severity: {severity:?}\n");
+            }
+            if let Some(elapsed) = frame.err_elapsed() {
+                out += &format!("   elapsed: {elapsed:?}\n");
+            }
+            for (key, value) in frame.err_fields() {
+                out += &format!("   {key}: {value}\n");
+            }
+            if let Some(timestamp) = frame.timestamp() {
+                out += &format!("   time: {}\n", fmt_timestamp(timestamp));
+            }
+        }
+        #[cfg(feature = "span-trace")]
+        if let Some(span_trace) = self.err_span_trace() {
+            out += &format!("span trace:\n{span_trace}\n");
+        }
+        if let Some(backtrace) = self.err_backtrace() {
+            out += &format!("stack backtrace:\n{backtrace}\n");
+        }
+        out
+    }
+}
+
+/// Format the time as an RFC 3339 UTC timestamp, with milliseconds.
+fn fmt_timestamp(time: std::time::SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+    // Civil date from days since the epoch, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Controls how errors are rendered by `Display`, `Debug` and
+/// [`StackError::render_report`].
+///
+/// A process-wide renderer is set with [`set_renderer`], so applications can
+/// enforce their own format, e.g. single line, logfmt or localized, across
+/// every error. Each method defaults to the rendering of
+/// [`DefaultRenderer`], so implementations only need to override what they
+/// change. Renderers should use [`StackError::frame_msg`] to get the message
+/// of each frame, as `Display` would call the renderer again.
+pub trait ErrorRenderer: Send + Sync {
+    /// Render the `Display` output. Defaults to the top message.
+    fn display(&self, error: &StackError, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", error.frame_msg())
+    }
+
+    /// Render the `Debug` output. Defaults to [`StackError::render`] with the
+    /// verbosity returned by [`verbosity`].
+    fn debug(&self, error: &StackError, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_error(error, verbosity(), f)
+    }
+
+    /// Render a report for the given width. Defaults to numbered frames
+    /// wrapped with hanging indents.
+    fn report(&self, error: &StackError, width: usize) -> String {
+        report(error, width)
+    }
+}
+
+/// The default [`ErrorRenderer`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRenderer;
+
+impl ErrorRenderer for DefaultRenderer {}
+
+static RENDERER: RwLock<Option<Arc<dyn ErrorRenderer>>> = RwLock::new(None);
+
+/// Set the process-wide renderer, replacing any previous one.
+pub fn set_renderer(renderer: impl ErrorRenderer + 'static) {
+    *RENDERER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(renderer));
+}
+
+/// Get the process-wide renderer.
+pub fn renderer() -> Arc<dyn ErrorRenderer> {
+    RENDERER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| Arc::new(DefaultRenderer))
+}
+
+fn report(error: &StackError, width: usize) -> String {
+    let mut frames: Vec<_> = std::iter::successors(Some(error), |e| {
+        std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+    })
+    .collect();
+    frames.reverse();
+    let label_width = (frames.len() - 1).to_string().len() + 2;
+    let mut out = String::new();
+    for (idx, frame) in frames.iter().enumerate() {
+        let label = format!("{idx}: ");
+        let message = frame.frame_msg().to_string();
+        let lines = wrap(&message, width.saturating_sub(label_width).max(1));
+        for (line_idx, line) in lines.iter().enumerate() {
+            let prefix = if line_idx == 0 { label.as_str() } else { "" };
+            out += &format!("{prefix:>label_width$}{line}\n");
+        }
+    }
+    out
+}
+
+/// Get the width of the terminal on stderr, or `None` if stderr isn't a
+/// terminal. With the `terminal` feature, the width is queried from the
+/// terminal. Otherwise, or if that fails, it is read from the `COLUMNS`
+/// environment variable, defaulting to 80.
+pub fn terminal_width() -> Option<usize> {
+    use std::io::IsTerminal;
+
+    if !std::io::stderr().is_terminal() {
+        return None;
+    }
+    #[cfg(all(feature = "terminal", any(unix, windows)))]
+    if let Some((terminal_size::Width(width), _)) =
+        terminal_size::terminal_size_of(std::io::stderr())
+    {
+        if width > 0 {
+            return Some(usize::from(width));
+        }
+    }
+    let width = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|width| *width > 0)
+        .unwrap_or(80);
+    Some(width)
+}
+
+/// Wrap the text at word boundaries to the given width. Line breaks in the
+/// text are kept, and words longer than the width are put on their own line.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += word;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Write the error with the given verbosity, as the default renderer. This
+/// also renders errors with other code types, which can't be passed to the
+/// [`ErrorRenderer`].
+pub(crate) fn fmt_error<C>(
+    error: &StackError<C>,
+    verbosity: Verbosity,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+{
+    if verbosity == Verbosity::Terse {
+        return write!(f, "{}", error.frame_msg());
+    }
+    let mut frames: Vec<_> = std::iter::successors(Some(error), |e| {
+        std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError<C>>())
+    })
+    .collect();
+    frames.reverse();
+    for (idx, frame) in frames.iter().enumerate() {
+        if idx > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{}", frame.frame_msg())?;
+        if frame.repeat_count() > 1 {
+            write!(f, " (x{})", frame.repeat_count())?;
+        }
+        fmt_branches(frame, f)?;
+    }
+    if verbosity == Verbosity::Full {
+        let mut details = Vec::new();
+        if let Some(code) = error.err_code() {
+            details.push(format!("code: {code:?}"));
+        }
+        if let Some(uri) = error.err_uri() {
+            details.push(format!("uri: {uri}"));
+        }
+        if let Some(hint) = error.err_hint() {
+            details.push(format!("hint: {hint:?}"));
+        }
+        if let Some(origin) = error.err_origin() {
+            details.push(format!("origin: {origin:?}"));
+        }
+        if let Some(severity) = error.err_severity() {
+            details.push(format!("severity: {severity:?}"));
+        }
+        for frame in &frames {
+            if let Some(location) = frame.err_location() {
+                details.push(format!("location: {location}"));
+            }
+            if let Some(position) = frame.err_position() {
+                details.push(format!("position: {position}"));
+            }
+            for (key, value) in frame.err_fields() {
+                details.push(format!("{key}: {value}"));
+            }
+        }
+        #[cfg(feature = "span-trace")]
+        if let Some(span_trace) = error.err_span_trace() {
+            details.push(format!("span trace:\n{span_trace}"));
+        }
+        if !details.is_empty() {
+            write!(f, "\n\n{}", details.join("\n"))?;
+        }
+    }
+    if f.alternate() || verbosity == Verbosity::Full {
+        if let Some(backtrace) = error.err_backtrace() {
+            write!(f, "\n\nstack backtrace:\n{backtrace}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the errors attached to the frame as branches, each with its chain.
+fn fmt_branches<C>(frame: &StackError<C>, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone + std::fmt::Debug,
+{
+    let branches: Vec<_> = frame.frame_attachments::<StackError<C>>().collect();
+    for (idx, branch) in branches.iter().enumerate() {
+        let last = idx + 1 == branches.len();
+        for (line_idx, line) in branch.render(Verbosity::Chain).lines().enumerate() {
+            let marker = match (line_idx, last) {
+                (0, false) => "├─ ",
+                (0, true) => "└─ ",
+                (_, false) => "│  ",
+                (_, true) => "   ",
+            };
+            write!(f, "\n{marker}{line}")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_render_verbosity() {
+        let line = line!();
+        let error = StackError::from_msg("Base error")
+            .with_no_err_capture()
+            .with_err_position(52, 3, 14)
+            .with_err_field("path", "config.toml")
+            .stack_err_msg("Stacked error")
+            .with_err_code(ErrorCode::IoNotFound);
+        assert_eq!(error.render(Verbosity::Terse), "Stacked error");
+        assert_eq!(error.render(Verbosity::Chain), "Base error\nStacked error");
+        assert_eq!(
+            error.render(Verbosity::Full),
+            format!(
+                "Base error\nStacked error\n\ncode: IoNotFound\nlocation: src/render.rs:{}:21\n\
+                 position: 3:14 (offset 52)\npath: config.toml\nlocation: src/render.rs:{}:14",
+                line + 1,
+                line + 5
+            )
+        );
+
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let error = StackError::from_panic("panicked".to_string(), Some(backtrace));
+        assert!(error
+            .render(Verbosity::Full)
+            .contains("\n\nstack backtrace:\n"));
+        assert!(!error.render(Verbosity::Chain).contains("stack backtrace"));
+    }
+
+    #[test]
+    fn test_render_attached_errors_as_tree() {
+        let nested = StackError::from_msg("c.txt: connection reset")
+            .attach(StackError::from_msg("retry 1 failed"))
+            .attach(StackError::from_msg("retry 2 failed"));
+        let error = StackError::from_msg("3 of 10 uploads failed")
+            .attach(StackError::from_msg("a.txt: timed out"))
+            .attach(StackError::from_msg("denied").stack_err_msg("b.txt: forbidden"))
+            .attach(nested)
+            .stack_err_msg("sync failed");
+        assert_eq!(
+            error.render(Verbosity::Chain),
+            concat!(
+                "3 of 10 uploads failed\n",
+                "├─ a.txt: timed out\n",
+                "├─ denied\n",
+                "│  b.txt: forbidden\n",
+                "└─ c.txt: connection reset\n",
+                "   ├─ retry 1 failed\n",
+                "   └─ retry 2 failed\n",
+                "sync failed"
+            )
+        );
+        assert_eq!(error.render(Verbosity::Terse), "sync failed");
+    }
+
+    #[test]
+    fn test_render_report_wraps() {
+        let error = StackError::from_msg("failed to read the configuration file from disk")
+            .stack_err_msg("startup failed");
+        assert_eq!(
+            error.render_report_width(24),
+            "0: failed to read the\n   configuration file\n   from disk\n1: startup failed\n"
+        );
+    }
+
+    struct TeapotRenderer;
+
+    impl ErrorRenderer for TeapotRenderer {
+        fn display(&self, error: &StackError, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            // Only affect errors used in this test, as the renderer is global
+            match error.err_code() {
+                Some(ErrorCode::HttpImATeapot) => {
+                    write!(f, "msg={:?}", error.frame_msg().to_string())
+                }
+                _ => DefaultRenderer.display(error, f),
+            }
+        }
+    }
+
+    #[test]
+    fn test_renderer_replaces_display() {
+        let error = StackError::from_msg("short and stout").with_err_code(ErrorCode::HttpImATeapot);
+        set_renderer(TeapotRenderer);
+        assert_eq!(error.to_string(), "msg=\"short and stout\"");
+        assert_eq!(format!("{:?}", error), "short and stout");
+        set_renderer(DefaultRenderer);
+        assert_eq!(error.to_string(), "short and stout");
+    }
+
+    #[test]
+    fn test_render_verbose() {
+        let line = line!();
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .stack_err_msg("Stacked error");
+        let lines: Vec<_> = error
+            .render_verbose()
+            .lines()
+            // Backtraces are captured with `RUST_BACKTRACE` and the
+            // `backtrace` feature
+            .take_while(|line| *line != "stack backtrace:")
+            .filter(|line| !line.starts_with("   time: "))
+            .map(str::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "0: Base error".to_string(),
+                "   code: IoNotFound".to_string(),
+                format!("   location: src/render.rs:{}:21", line + 1),
+                "   path: config.toml".to_string(),
+                "1: Stacked error".to_string(),
+                "   code: IoNotFound".to_string(),
+                format!("   location: src/render.rs:{}:14", line + 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fmt_timestamp() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_709_210_096_789);
+        assert_eq!(fmt_timestamp(time), "2024-02-29T12:34:56.789Z");
+        assert_eq!(
+            fmt_timestamp(std::time::UNIX_EPOCH),
+            "1970-01-01T00:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn test_verbosity_parses() {
+        assert_eq!(" Full".parse(), Ok(Verbosity::Full));
+        assert_eq!("loud".parse::<Verbosity>(), Err(UnknownVerbosity));
+    }
+}
@@ -0,0 +1,113 @@
+//! Provides the [`StackResultExt`] trait with combinators for results whose
+//! error implements [`ErrorStacks`].
+
+use crate::error::ErrorStacks;
+
+/// Extension trait for results whose error implements [`ErrorStacks`].
+pub trait StackResultExt<T, E, C>: Sized
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    E: ErrorStacks<C>,
+{
+    /// Recover from an error with the given code by calling `f`, and return
+    /// other errors unchanged:
+    ///
+    /// ```rust
+    /// let config = read_config(&path).or_recover_with_code(ErrorCode::IoNotFound, |_| {
+    ///     write_default_config(&path)
+    /// })?;
+    /// ```
+    fn or_recover_with_code(self, code: C, f: impl FnOnce(E) -> Result<T, E>) -> Result<T, E>;
+
+    /// Call `f` with a reference to the error, if any, e.g. to count it in
+    /// metrics, and return the result unchanged.
+    fn inspect_err_stack(self, f: impl FnOnce(&E)) -> Self;
+
+    /// Log the error, if any, at the given level with its `Debug` output, and
+    /// return the result unchanged.
+    #[cfg(feature = "log")]
+    fn tap_err_log(self, level: log::Level) -> Self
+    where
+        E: std::fmt::Debug,
+    {
+        self.inspect_err_stack(|error| log::log!(level, "{error:?}"))
+    }
+
+    /// [Emit](crate::error::StackError::emit) the error, if any, at the level
+    /// chosen from its code, and return the result unchanged:
+    ///
+    /// ```rust
+    /// let Ok(user) = fetch_user(id).await.emit_err() else {
+    ///     return Response::error();
+    /// };
+    /// ```
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    fn emit_err(self) -> Self
+    where
+        E: std::borrow::Borrow<crate::error::StackError>;
+}
+
+impl<T, E, C> StackResultExt<T, E, C> for Result<T, E>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    E: ErrorStacks<C>,
+{
+    fn or_recover_with_code(self, code: C, f: impl FnOnce(E) -> Result<T, E>) -> Result<T, E> {
+        match self {
+            Err(error) if error.err_code() == Some(&code) => f(error),
+            result => result,
+        }
+    }
+
+    fn inspect_err_stack(self, f: impl FnOnce(&E)) -> Self {
+        if let Err(error) = &self {
+            f(error);
+        }
+        self
+    }
+
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    #[track_caller]
+    fn emit_err(self) -> Self
+    where
+        E: std::borrow::Borrow<crate::error::StackError>,
+    {
+        if let Err(error) = &self {
+            error.borrow().emit();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn read(code: ErrorCode) -> StackResult<&'static str> {
+        Err(StackError::from_msg("Base error").with_err_code(code))
+    }
+
+    #[test]
+    fn test_or_recover_with_code() {
+        let result = read(ErrorCode::IoNotFound)
+            .or_recover_with_code(ErrorCode::IoNotFound, |_| Ok("default"));
+        assert_eq!(result.unwrap(), "default");
+        let result = read(ErrorCode::IoPermissionDenied)
+            .or_recover_with_code(ErrorCode::IoNotFound, |_| Ok("default"));
+        assert_eq!(result.err_code(), Some(&ErrorCode::IoPermissionDenied));
+    }
+
+    #[test]
+    fn test_inspect_err_stack() {
+        let mut count = 0;
+        let result = read(ErrorCode::IoNotFound)
+            .inspect_err_stack(|_| count += 1)
+            .stack_err_msg("Stacked error");
+        assert_eq!(count, 1);
+        assert_eq!(
+            format!("{:?}", result.unwrap_err()),
+            "Base error\nStacked error"
+        );
+    }
+}
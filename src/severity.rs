@@ -0,0 +1,24 @@
+//! Provides the [`Severity`] type, telling how serious an error is.
+
+/// How serious an error is, e.g. to choose the level at which it is logged
+/// and whether it triggers alerts. Severities are ordered from the least to
+/// the most serious.
+///
+/// Severities are set with
+/// [`ErrorStacks::with_err_severity`](crate::error::ErrorStacks::with_err_severity)
+/// and are inherited by stacked frames, as are codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Severity {
+    /// Expected errors which are only of interest when debugging.
+    Debug,
+    /// Expected errors which are part of normal operation.
+    Info,
+    /// Errors which are recovered from, but may need attention.
+    Warn,
+    /// Errors which failed an operation.
+    Error,
+    /// Errors after which the program can't continue.
+    Fatal,
+}
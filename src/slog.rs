@@ -0,0 +1,82 @@
+//! Provides `slog` key-value support for [`StackError`].
+//!
+//! As `slog` keys are static, the fields of an error are not included in its
+//! key-values. Use [`StackError::kv_pairs`] to log them.
+
+use crate::error::{ErrorStacks, StackError};
+
+/// Serializes the chain under the given key, followed by the `code` and `uri`
+/// if set.
+impl ::slog::Value for StackError {
+    fn serialize(
+        &self,
+        _record: &::slog::Record,
+        key: ::slog::Key,
+        serializer: &mut dyn ::slog::Serializer,
+    ) -> ::slog::Result {
+        serializer.emit_arguments(key, &format_args!("{self:?}"))?;
+        if let Some(code) = self.err_code() {
+            serializer.emit_arguments("code", &format_args!("{code:?}"))?;
+        }
+        if let Some(uri) = self.err_uri() {
+            serializer.emit_str("uri", uri)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes the chain under the `error` key, followed by the `code` and
+/// `uri` if set.
+impl ::slog::KV for StackError {
+    fn serialize(
+        &self,
+        record: &::slog::Record,
+        serializer: &mut dyn ::slog::Serializer,
+    ) -> ::slog::Result {
+        ::slog::Value::serialize(self, record, "error", serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[derive(Default)]
+    struct Collect(Vec<(String, String)>);
+
+    impl ::slog::Serializer for Collect {
+        fn emit_arguments(
+            &mut self,
+            key: ::slog::Key,
+            val: &std::fmt::Arguments,
+        ) -> ::slog::Result {
+            self.0.push((key.to_string(), val.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_slog_kv() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .stack_err_msg("Stacked error")
+            .with_err_uri("https://example.com/stacked".to_string());
+        let record_static = ::slog::record_static!(::slog::Level::Error, "");
+        let mut collect = Collect::default();
+        ::slog::KV::serialize(
+            &error,
+            &::slog::Record::new(&record_static, &format_args!(""), ::slog::b!()),
+            &mut collect,
+        )
+        .unwrap();
+        assert_eq!(
+            collect.0,
+            vec![
+                ("error".to_string(), "Base error\nStacked error".to_string()),
+                ("code".to_string(), "IoNotFound".to_string()),
+                ("uri".to_string(), "https://example.com/stacked".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Provides the [`ErrorSpan`] and [`ErrorPosition`] attachments for pointing
+//! at input locations.
+
+use std::ops::Range;
+
+/// A byte range in a named source, such as a file being parsed.
+///
+/// Spans are attached to frames with
+/// [`ErrorStacks::with_err_span`](crate::error::ErrorStacks::with_err_span),
+/// so that renderers can label the exact input location of an error.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ErrorSpan {
+    /// The name of the source, e.g. a file path.
+    pub source_name: String,
+    /// The byte range in the source.
+    pub range: Range<usize>,
+}
+
+impl ErrorSpan {
+    /// Creates a new span.
+    pub fn new(source_name: impl Into<String>, range: Range<usize>) -> Self {
+        Self {
+            source_name: source_name.into(),
+            range,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}[{}..{}]",
+            self.source_name, self.range.start, self.range.end
+        )
+    }
+}
+
+/// A position in an input, such as a file or stream being processed.
+///
+/// A position is attached to a frame with
+/// [`ErrorStacks::with_err_position`](crate::error::ErrorStacks::with_err_position).
+/// Lines and columns are 1-based, and the offset is in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ErrorPosition {
+    /// The byte offset in the input.
+    pub offset: usize,
+    /// The line number.
+    pub line: usize,
+    /// The column number.
+    pub column: usize,
+}
+
+impl ErrorPosition {
+    /// Creates a new position.
+    pub fn new(offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{} (offset {})", self.line, self.column, self.offset)
+    }
+}
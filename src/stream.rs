@@ -0,0 +1,73 @@
+//! Provides the [`StackStreamExt`] trait for annotating errors on streams.
+
+use futures_util::stream::{Stream, TryStream, TryStreamExt};
+
+use crate::error::ErrorStacks;
+
+/// Extension trait for streams yielding [`Result`] items whose error
+/// implements [`ErrorStacks`].
+///
+/// The adapters are applied to every error item, and `Ok` items pass through
+/// unchanged.
+pub trait StackStreamExt<C>: TryStream + Sized
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    Self::Error: ErrorStacks<C>,
+{
+    /// Stack a new error with the given message on every error item. The
+    /// frames have the location of the caller.
+    #[track_caller]
+    fn stack_err_msg(
+        self,
+        error: impl std::fmt::Display + Clone + Send + Sync + 'static,
+    ) -> impl Stream<Item = Result<Self::Ok, Self::Error>> {
+        let location = std::panic::Location::caller();
+        self.into_stream()
+            .map_err(move |e| e.stack_err_msg(error.clone()).with_err_location(location))
+    }
+
+    /// Set the error code on every error item.
+    fn with_err_code(self, code: C) -> impl Stream<Item = Result<Self::Ok, Self::Error>> {
+        self.into_stream()
+            .map_err(move |e| e.with_err_code(code.clone()))
+    }
+}
+
+impl<S, C> StackStreamExt<C> for S
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+    S: TryStream,
+    S::Error: ErrorStacks<C>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use futures_util::{FutureExt, StreamExt};
+
+    #[test]
+    fn test_stream_stacks_every_error() {
+        let items: Vec<StackResult<u32>> = vec![
+            Ok(1),
+            Err(StackError::from_msg("First error")),
+            Err(StackError::from_msg("Second error")),
+        ];
+        let line = line!() + 2;
+        let results: Vec<_> = futures_util::stream::iter(items)
+            .stack_err_msg("Stacked error")
+            .with_err_code(ErrorCode::IoInvalidData)
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(results[0].as_ref().ok(), Some(&1));
+        assert_eq!(results[1].err_code(), Some(&ErrorCode::IoInvalidData));
+        assert_eq!(
+            format!("{:?}", results[2].as_ref().unwrap_err()),
+            "Second error\nStacked error"
+        );
+        let location = results[2].as_ref().unwrap_err().err_location().unwrap();
+        assert_eq!((location.file(), location.line()), ("src/stream.rs", line));
+    }
+}
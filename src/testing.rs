@@ -0,0 +1,124 @@
+//! Provides utilities for testing the shape of errors.
+
+use crate::error::{ErrorStacks, StackError};
+
+/// Compare the frames of two errors, from the root to the top, and describe
+/// how the actual error differs from the expected one. The messages, codes,
+/// URIs and fields of frames are compared. Returns `None` if they are the
+/// same.
+///
+/// The description has a line per difference, prefixed with the index of the
+/// frame:
+///
+/// ```text
+/// frame 1: code: expected Some(IoNotFound), got None
+/// frame 2: unexpected frame "retrying"
+/// ```
+pub fn diff_errors(expected: &StackError, actual: &StackError) -> Option<String> {
+    let expected = frames(expected);
+    let actual = frames(actual);
+    let mut lines = Vec::new();
+    for idx in 0..expected.len().max(actual.len()) {
+        let frame = match (expected.get(idx), actual.get(idx)) {
+            (Some(expected), Some(actual)) => diff_frame(expected, actual),
+            (Some(expected), None) => {
+                vec![format!(
+                    "missing frame {:?}",
+                    expected.frame_msg().to_string()
+                )]
+            }
+            (None, Some(actual)) => {
+                vec![format!(
+                    "unexpected frame {:?}",
+                    actual.frame_msg().to_string()
+                )]
+            }
+            (None, None) => unreachable!("the index is below one of the lengths"),
+        };
+        lines.extend(frame.into_iter().map(|line| format!("frame {idx}: {line}")));
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+fn frames(error: &StackError) -> Vec<&StackError> {
+    let mut frames: Vec<_> = std::iter::successors(Some(error), |e| {
+        std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+    })
+    .collect();
+    frames.reverse();
+    frames
+}
+
+fn diff_frame(expected: &StackError, actual: &StackError) -> Vec<String> {
+    let mut lines = Vec::new();
+    let (expected_msg, actual_msg) = (
+        expected.frame_msg().to_string(),
+        actual.frame_msg().to_string(),
+    );
+    if expected_msg != actual_msg {
+        lines.push(format!(
+            "message: expected {expected_msg:?}, got {actual_msg:?}"
+        ));
+    }
+    if expected.err_code() != actual.err_code() {
+        lines.push(format!(
+            "code: expected {:?}, got {:?}",
+            expected.err_code(),
+            actual.err_code()
+        ));
+    }
+    if expected.err_uri() != actual.err_uri() {
+        lines.push(format!(
+            "uri: expected {:?}, got {:?}",
+            expected.err_uri(),
+            actual.err_uri()
+        ));
+    }
+    if expected.err_fields() != actual.err_fields() {
+        lines.push(format!(
+            "fields: expected {:?}, got {:?}",
+            expected.err_fields(),
+            actual.err_fields()
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[test]
+    fn test_diff_errors() {
+        let expected = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .stack_err_msg("Stacked error");
+        assert_eq!(
+            diff_errors(
+                &expected,
+                &StackError::from_msg("Base error")
+                    .with_err_code(ErrorCode::IoNotFound)
+                    .with_err_field("path", "config.toml")
+                    .stack_err_msg("Stacked error")
+            ),
+            None
+        );
+
+        let actual = StackError::from_msg("Base error")
+            .with_err_field("path", "data.toml")
+            .stack_err_msg("Other error")
+            .stack_err_msg("Retrying");
+        assert_eq!(
+            diff_errors(&expected, &actual).unwrap(),
+            concat!(
+                "frame 0: code: expected Some(IoNotFound), got None\n",
+                "frame 0: fields: expected [(\"path\", \"config.toml\")], got [(\"path\", \"data.toml\")]\n",
+                "frame 1: message: expected \"Stacked error\", got \"Other error\"\n",
+                "frame 1: code: expected Some(IoNotFound), got None\n",
+                "frame 2: unexpected frame \"Retrying\""
+            )
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Provides the [`ErrorThrottle`] for deduplicating repeated errors in logs.
+
+use std::collections::{hash_map, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::StackError;
+
+/// Decision returned by [`ErrorThrottle::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleDecision {
+    /// The error should be reported. `repeated` is the number of identical
+    /// errors suppressed since it was last reported.
+    Emit { repeated: u64 },
+    /// The error was already reported within the window, and should be
+    /// suppressed.
+    Suppress,
+}
+
+#[derive(Debug)]
+struct Entry {
+    emitted_at: Instant,
+    repeated: u64,
+}
+
+/// Suppresses identical errors reported within a time window.
+///
+/// Errors are identified by their [fingerprint](StackError::err_fingerprint).
+/// The first occurrence of an error is emitted, and further occurrences
+/// within the window are suppressed. The next occurrence after the window
+/// is emitted along with the number of suppressed occurrences, so the
+/// caller can log a summary:
+///
+/// ```rust
+/// static THROTTLE: LazyLock<ErrorThrottle> =
+///     LazyLock::new(|| ErrorThrottle::new(Duration::from_secs(60)));
+///
+/// match THROTTLE.check(&err) {
+///     ThrottleDecision::Emit { repeated: 0 } => log::error!("{err:?}"),
+///     ThrottleDecision::Emit { repeated } => {
+///         log::error!("{err:?} (last error repeated {repeated} times)")
+///     }
+///     ThrottleDecision::Suppress => {}
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ErrorThrottle {
+    window: Duration,
+    entries: Mutex<HashMap<u64, Entry>>,
+}
+
+impl ErrorThrottle {
+    /// Creates a throttle with the given window.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record an occurrence of the error, and decide whether to report it.
+    pub fn check(&self, error: &StackError) -> ThrottleDecision {
+        self.check_at(error, Instant::now())
+    }
+
+    /// Like [`ErrorThrottle::check`], at the given time.
+    pub fn check_at(&self, error: &StackError, now: Instant) -> ThrottleDecision {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget expired errors which have nothing left to summarize
+        entries.retain(|_, entry| {
+            entry.repeated > 0 || now.duration_since(entry.emitted_at) < self.window
+        });
+        let entry = match entries.entry(error.err_fingerprint()) {
+            hash_map::Entry::Vacant(vacant) => {
+                vacant.insert(Entry {
+                    emitted_at: now,
+                    repeated: 0,
+                });
+                return ThrottleDecision::Emit { repeated: 0 };
+            }
+            hash_map::Entry::Occupied(occupied) => occupied.into_mut(),
+        };
+        if now.duration_since(entry.emitted_at) < self.window {
+            entry.repeated += 1;
+            ThrottleDecision::Suppress
+        } else {
+            let repeated = entry.repeated;
+            *entry = Entry {
+                emitted_at: now,
+                repeated: 0,
+            };
+            ThrottleDecision::Emit { repeated }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_suppresses_repeats() {
+        let throttle = ErrorThrottle::new(Duration::from_secs(10));
+        let error = StackError::from_msg("Test error");
+        let other = StackError::from_msg("Other error");
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(
+            throttle.check_at(&error, at(0)),
+            ThrottleDecision::Emit { repeated: 0 }
+        );
+        assert_eq!(throttle.check_at(&error, at(1)), ThrottleDecision::Suppress);
+        assert_eq!(throttle.check_at(&error, at(2)), ThrottleDecision::Suppress);
+        assert_eq!(
+            throttle.check_at(&other, at(3)),
+            ThrottleDecision::Emit { repeated: 0 }
+        );
+        assert_eq!(
+            throttle.check_at(&error, at(11)),
+            ThrottleDecision::Emit { repeated: 2 }
+        );
+        assert_eq!(
+            throttle.check_at(&other, at(30)),
+            ThrottleDecision::Emit { repeated: 0 }
+        );
+    }
+}
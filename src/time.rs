@@ -0,0 +1,60 @@
+//! Provides [`timeout`] for running futures with a time budget, and the
+//! conversion of tokio's timeout error into `StackError`.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+
+/// Converts with the `IoTimedOut` code. The elapsed time isn't known, use
+/// [`timeout`] to record it.
+impl From<tokio::time::error::Elapsed> for StackError {
+    fn from(error: tokio::time::error::Elapsed) -> Self {
+        StackError::from_err(error).with_err_code(ErrorCode::IoTimedOut)
+    }
+}
+
+/// Run a future with a time budget, as `tokio::time::timeout`.
+///
+/// If the budget is exceeded, the error has the `IoTimedOut` code, and the
+/// elapsed time and the deadline are set, so that it shows how far over
+/// budget the operation was:
+///
+/// ```rust
+/// let body = timeout(Duration::from_secs(5), fetch(url)).await??;
+/// ```
+pub async fn timeout<F: Future>(budget: Duration, future: F) -> Result<F::Output, StackError> {
+    let start = Instant::now();
+    let deadline = start + budget;
+    tokio::time::timeout_at(deadline.into(), future)
+        .await
+        .map_err(|error| {
+            StackError::from(error)
+                .with_err_elapsed(start.elapsed())
+                .with_err_deadline(deadline)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_sets_timing() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let budget = Duration::from_millis(10);
+        let error = runtime
+            .block_on(timeout(budget, std::future::pending::<()>()))
+            .unwrap_err();
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoTimedOut));
+        assert!(error.err_elapsed().unwrap() >= budget);
+        assert!(error.err_deadline().unwrap() <= Instant::now());
+
+        let value = runtime.block_on(timeout(budget, std::future::ready(42)));
+        assert_eq!(value.unwrap(), 42);
+    }
+}
@@ -0,0 +1,318 @@
+//! Provides the [`StackErrorTiny`] error, which stores its frames without
+//! allocating, and the [`ErrorStacksCore`] trait, which it shares with
+//! [`StackError`].
+//!
+//! Both only use `core`, so they are available without the `std` feature,
+//! on targets without an allocator. Creating and stacking the error never
+//! allocates, and it has the same stacking patterns as [`StackError`]. With
+//! `std`, it is also meant for allocation-sensitive hot paths, where stacking
+//! a [`StackError`] frame allocates, and it converts with `?` where a
+//! [`StackResult`] is returned:
+//!
+//! ```rust
+//! use stackerror::tiny::ErrorStacksCore;
+//!
+//! fn decode(input: &[u8]) -> Result<Frame, StackErrorTiny> {
+//!     let header = decode_header(input).stack_err_msg("decoding header")?;
+//!     ...
+//! }
+//!
+//! fn handle(input: &[u8]) -> StackResult<()> {
+//!     let frame = decode(input)?;
+//!     ...
+//! }
+//! ```
+//!
+//! [`StackError`]: crate::error::StackError
+//! [`StackResult`]: crate::prelude::StackResult
+
+use core::fmt;
+
+use crate::codes::ErrorCode;
+#[cfg(feature = "std")]
+use crate::error::{ErrorStacks, StackError};
+
+/// The subset of the [`ErrorStacks`] methods which only use `core`: codes,
+/// static messages and stacking. It is implemented by [`StackErrorTiny`],
+/// [`StackError`] and results of either, so code generic over it works with
+/// both.
+///
+/// It isn't in the prelude, as its methods have the same names as those of
+/// [`ErrorStacks`].
+///
+/// [`ErrorStacks`]: crate::error::ErrorStacks
+/// [`StackError`]: crate::error::StackError
+pub trait ErrorStacksCore<C>: Sized {
+    /// Get the error code if one is set.
+    fn err_code(&self) -> Option<&C>;
+    /// Set the error code.
+    fn with_err_code(self, code: C) -> Self;
+    /// Stack a new error on the current one with a given message, at the
+    /// location of the caller.
+    #[track_caller]
+    fn stack_err_msg(self, message: &'static str) -> Self;
+}
+
+/// Implementation for [`Result`] allows adding error codes on results.
+impl<T, E, C> ErrorStacksCore<C> for Result<T, E>
+where
+    E: ErrorStacksCore<C>,
+{
+    fn err_code(&self) -> Option<&C> {
+        self.as_ref().err().and_then(|e| e.err_code())
+    }
+
+    fn with_err_code(self, code: C) -> Self {
+        self.map_err(|e| e.with_err_code(code))
+    }
+
+    #[track_caller]
+    fn stack_err_msg(self, message: &'static str) -> Self {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.stack_err_msg(message)),
+        }
+    }
+}
+
+/// An error storing up to `N` static messages and a code inline.
+///
+/// It implements [`ErrorStacksCore`], the subset of the
+/// [`ErrorStacks`](crate::error::ErrorStacks) methods which don't require
+/// allocating: codes, static messages and stacking. When all `N`
+/// frames are used, stacking replaces the top frame, so that the root cause
+/// and the latest context are kept, and the number of dropped frames is
+/// counted. The default capacity of 4 frames keeps the error small enough to
+/// be returned by value.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct StackErrorTiny<const N: usize = 4> {
+    messages: [&'static str; N],
+    len: usize,
+    dropped: usize,
+    code: Option<ErrorCode>,
+}
+
+impl<const N: usize> StackErrorTiny<N> {
+    /// Creates a new error with the given message.
+    pub const fn from_msg(message: &'static str) -> Self {
+        let mut messages = [""; N];
+        let len = if N > 0 {
+            messages[0] = message;
+            1
+        } else {
+            0
+        };
+        Self {
+            messages,
+            len,
+            dropped: if N > 0 { 0 } else { 1 },
+            code: None,
+        }
+    }
+
+    /// Get the error code if one is set.
+    pub fn err_code(&self) -> Option<&ErrorCode> {
+        self.code.as_ref()
+    }
+
+    /// Set the error code.
+    pub fn with_err_code(self, code: ErrorCode) -> Self {
+        Self {
+            code: Some(code),
+            ..self
+        }
+    }
+
+    /// Remove the error code.
+    pub fn with_no_err_code(self) -> Self {
+        Self { code: None, ..self }
+    }
+
+    /// Replace the message of the top frame.
+    pub fn with_err_msg(mut self, message: &'static str) -> Self {
+        match self.len {
+            0 => self.dropped += 1,
+            len => self.messages[len - 1] = message,
+        }
+        self
+    }
+
+    /// Stack a new frame with the given message on the current ones.
+    pub fn stack_err_msg(mut self, message: &'static str) -> Self {
+        if self.len < N {
+            self.messages[self.len] = message;
+            self.len += 1;
+        } else {
+            self.dropped += 1;
+            self = self.with_err_msg(message);
+        }
+        self
+    }
+
+    /// Get the messages of the frames, from the root to the top.
+    pub fn messages(&self) -> &[&'static str] {
+        &self.messages[..self.len]
+    }
+
+    /// Get the number of frames which were dropped as the capacity was
+    /// reached.
+    pub fn dropped_frames(&self) -> usize {
+        self.dropped
+    }
+}
+
+/// Displays the message of the top frame.
+impl<const N: usize> fmt::Display for StackErrorTiny<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.messages().last().copied().unwrap_or_default())
+    }
+}
+
+/// Displays the messages from the root to the top, one per line, and the
+/// number of dropped frames.
+impl<const N: usize> fmt::Debug for StackErrorTiny<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, message) in self.messages().iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            if idx + 1 == self.len && self.dropped > 0 {
+                writeln!(f, "({} frames dropped)", self.dropped)?;
+            }
+            write!(f, "{message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> core::error::Error for StackErrorTiny<N> {}
+
+impl<const N: usize> ErrorStacksCore<ErrorCode> for StackErrorTiny<N> {
+    fn err_code(&self) -> Option<&ErrorCode> {
+        self.code.as_ref()
+    }
+
+    fn with_err_code(self, code: ErrorCode) -> Self {
+        StackErrorTiny::with_err_code(self, code)
+    }
+
+    fn stack_err_msg(self, message: &'static str) -> Self {
+        StackErrorTiny::stack_err_msg(self, message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C> ErrorStacksCore<C> for StackError<C>
+where
+    C: Send + Sync + 'static + Eq + PartialEq + Clone,
+{
+    fn err_code(&self) -> Option<&C> {
+        ErrorStacks::err_code(self)
+    }
+
+    fn with_err_code(self, code: C) -> Self {
+        ErrorStacks::with_err_code(self, code)
+    }
+
+    #[track_caller]
+    fn stack_err_msg(self, message: &'static str) -> Self {
+        ErrorStacks::stack_err_msg(self, message)
+    }
+}
+
+/// Converts to a [`StackError`] with one frame per message, and the code on
+/// all frames.
+#[cfg(feature = "std")]
+impl<const N: usize> From<StackErrorTiny<N>> for StackError {
+    fn from(error: StackErrorTiny<N>) -> Self {
+        let mut messages = error.messages().iter();
+        let mut stacked = match messages.next() {
+            Some(message) => StackError::from_msg(*message),
+            None => StackError::empty(),
+        };
+        if let Some(code) = error.code {
+            stacked = ErrorStacks::with_err_code(stacked, code);
+        }
+        messages.fold(stacked, |stacked, message| {
+            ErrorStacks::stack_err_msg(stacked, *message)
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiny_stacks() {
+        let error = StackErrorTiny::<4>::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .stack_err_msg("Stacked error");
+        assert_eq!(error.messages(), ["Base error", "Stacked error"]);
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+        assert_eq!(error.to_string(), "Stacked error");
+        assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+
+        let stacked = StackError::from(error);
+        assert_eq!(format!("{:?}", stacked), "Base error\nStacked error");
+        assert_eq!(
+            ErrorStacks::err_code(&stacked),
+            Some(&ErrorCode::IoNotFound)
+        );
+    }
+
+    /// Annotates the result through the core trait, for either error type.
+    fn decode<E: ErrorStacksCore<ErrorCode>>(result: Result<u8, E>) -> Result<u8, E> {
+        result
+            .with_err_code(ErrorCode::IoUnexpectedEof)
+            .stack_err_msg("decoding header")
+    }
+
+    #[test]
+    fn test_core_trait_is_shared() {
+        let error = decode(Err(StackErrorTiny::<4>::from_msg("empty input"))).unwrap_err();
+        assert_eq!(error.messages(), ["empty input", "decoding header"]);
+        assert_eq!(error.err_code(), Some(&ErrorCode::IoUnexpectedEof));
+
+        let error = decode(Err(StackError::from_msg("empty input"))).unwrap_err();
+        assert_eq!(format!("{:?}", error), "empty input\ndecoding header");
+        assert_eq!(
+            ErrorStacksCore::err_code(&error),
+            Some(&ErrorCode::IoUnexpectedEof)
+        );
+        assert_eq!(decode(Ok::<_, StackError>(7)).unwrap(), 7);
+    }
+
+    fn handle(input: &[u8]) -> crate::prelude::StackResult<u8> {
+        let first = input
+            .first()
+            .copied()
+            .ok_or(StackErrorTiny::<4>::from_msg("empty input"));
+        Ok(decode(first)?)
+    }
+
+    #[test]
+    fn test_tiny_result_converts() {
+        assert_eq!(handle(&[7]).unwrap(), 7);
+        let error = handle(&[]).unwrap_err();
+        assert_eq!(format!("{:?}", error), "empty input\ndecoding header");
+        assert_eq!(
+            ErrorStacks::err_code(&error),
+            Some(&ErrorCode::IoUnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn test_tiny_drops_frames() {
+        let error = StackErrorTiny::<2>::from_msg("Base error")
+            .stack_err_msg("First error")
+            .stack_err_msg("Second error")
+            .stack_err_msg("Third error");
+        assert_eq!(error.messages(), ["Base error", "Third error"]);
+        assert_eq!(error.dropped_frames(), 2);
+        assert_eq!(
+            format!("{:?}", error),
+            "Base error\n(2 frames dropped)\nThird error"
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! Provides a `tracing` layer which renders stacked errors recorded on
+//! events, and the capture of the current span into new errors.
+//!
+//! Errors created inside a span record its ID and the ID of its trace, see
+//! [`StackError::err_trace_id`]. The fields of the span selected with a
+//! [`SpanFieldsLayer`] are also recorded as error fields.
+//!
+//! With the `span-trace` feature, errors also capture a
+//! [`SpanTrace`](tracing_error::SpanTrace) of the entered spans, see
+//! [`StackError::err_span_trace`]. This gives async code a trace of where the
+//! error occurred, which backtraces can't provide across await points. It
+//! requires adding a `tracing_error::ErrorLayer` to the subscriber.
+
+use std::io::Write;
+
+use ::tracing::field::{Field, Visit};
+use ::tracing::span::{Attributes, Id, Record};
+use ::tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+use crate::error::{ErrorStacks, StackError};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+
+/// A [`Layer`] which renders events carrying a [`StackError`].
+///
+/// Errors must be recorded as `dyn Error` values so that the layer can find
+/// them, e.g. `tracing::error!(error = &err as &dyn std::error::Error, "...")`.
+/// The event message is written followed by the chain from the root to the
+/// top, and the code, URI and fields of the error. Events without a
+/// [`StackError`] are ignored, so this is meant to be paired with a filter
+/// excluding those events from the application's formatting layer.
+#[derive(Debug)]
+pub struct StackErrorLayer<W = fn() -> std::io::Stderr> {
+    make_writer: W,
+    ansi: bool,
+}
+
+impl StackErrorLayer {
+    /// Creates a layer writing to stderr, with colors.
+    pub fn new() -> Self {
+        Self {
+            make_writer: std::io::stderr,
+            ansi: true,
+        }
+    }
+}
+
+impl Default for StackErrorLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> StackErrorLayer<W> {
+    /// Set the writer the layer writes to.
+    pub fn with_writer<W2>(self, make_writer: W2) -> StackErrorLayer<W2>
+    where
+        W2: for<'a> MakeWriter<'a> + 'static,
+    {
+        StackErrorLayer {
+            make_writer,
+            ansi: self.ansi,
+        }
+    }
+
+    /// Set whether ANSI colors are written.
+    pub fn with_ansi(self, ansi: bool) -> Self {
+        Self { ansi, ..self }
+    }
+}
+
+/// Render the chain, code, URI and fields of the error, one per line.
+fn render_error(error: &StackError, ansi: bool) -> String {
+    let style = |style: &'static str| if ansi { style } else { "" };
+    let frames: Vec<_> = std::iter::successors(Some(error), |e| {
+        std::error::Error::source(*e).and_then(|s| s.downcast_ref::<StackError>())
+    })
+    .collect();
+    let mut out = String::new();
+    for (idx, frame) in frames.iter().rev().enumerate() {
+        let message = frame.frame_msg();
+        out += &format!("  {}{idx}:{} {message}\n", style(DIM), style(RESET));
+    }
+    if let Some(code) = error.err_code() {
+        out += &format!("  {}code:{} {code:?}\n", style(BOLD), style(RESET));
+    }
+    if let Some(uri) = error.err_uri() {
+        out += &format!("  {}uri:{} {uri}\n", style(BOLD), style(RESET));
+    }
+    if let Some(trace_id) = error.err_trace_id() {
+        out += &format!("  {}trace_id:{} {trace_id}\n", style(BOLD), style(RESET));
+    }
+    for frame in frames.iter().rev() {
+        for (key, value) in frame.err_fields() {
+            out += &format!("  {}{key}:{} {value}\n", style(DIM), style(RESET));
+        }
+    }
+    out
+}
+
+struct EventVisitor {
+    ansi: bool,
+    message: String,
+    error: Option<String>,
+}
+
+impl Visit for EventVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        }
+    }
+
+    fn record_error(&mut self, _field: &Field, value: &(dyn std::error::Error + 'static)) {
+        // The error is only borrowed while visiting, so it's rendered here
+        if let (None, Some(error)) = (&self.error, value.downcast_ref::<StackError>()) {
+            self.error = Some(render_error(error, self.ansi));
+        }
+    }
+}
+
+impl<S, W> Layer<S> for StackErrorLayer<W>
+where
+    S: Subscriber,
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = EventVisitor {
+            ansi: self.ansi,
+            message: String::new(),
+            error: None,
+        };
+        event.record(&mut visitor);
+        let Some(error) = visitor.error else {
+            return;
+        };
+        let level = event.metadata().level();
+        let (level_style, reset) = match (self.ansi, *level) {
+            (false, _) => ("", ""),
+            (true, Level::ERROR) => (RED, RESET),
+            (true, Level::WARN) => (YELLOW, RESET),
+            (true, _) => (BOLD, RESET),
+        };
+        let out = format!("{level_style}{level}{reset} {}\n{error}", visitor.message);
+        let _ = self.make_writer.make_writer().write_all(out.as_bytes());
+    }
+}
+
+/// The name of the span field holding the ID of the distributed trace.
+const TRACE_ID_FIELD: &str = "trace_id";
+
+/// A [`Layer`] which records selected fields of spans, so that errors created
+/// inside a span carry them as fields, from the outermost span to the
+/// innermost one. It must be added to a [`Registry`].
+///
+/// The layer also records the `trace_id` field of spans, which is used as the
+/// error's [trace ID](StackError::err_trace_id), e.g. to propagate the ID of
+/// an incoming request's trace:
+///
+/// ```rust
+/// tracing_subscriber::registry()
+///     .with(SpanFieldsLayer::new(["request_id", "tenant"]))
+///     .init();
+/// let span = tracing::info_span!("request", trace_id = %traceparent.trace_id, request_id = %id);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SpanFieldsLayer {
+    fields: Vec<&'static str>,
+}
+
+impl SpanFieldsLayer {
+    /// Creates a layer recording the span fields with the given names.
+    pub fn new(fields: impl IntoIterator<Item = &'static str>) -> Self {
+        Self {
+            fields: fields.into_iter().collect(),
+        }
+    }
+}
+
+/// The fields recorded on a span by [`SpanFieldsLayer`], stored in its
+/// extensions.
+#[derive(Default)]
+struct SpanFields {
+    trace_id: Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+struct SpanFieldsVisitor<'a> {
+    names: &'a [&'static str],
+    recorded: &'a mut SpanFields,
+}
+
+impl SpanFieldsVisitor<'_> {
+    fn record(&mut self, field: &Field, value: String) {
+        let name = field.name();
+        if name == TRACE_ID_FIELD {
+            self.recorded.trace_id = Some(value.clone());
+        }
+        if !self.names.contains(&name) {
+            return;
+        }
+        match self.recorded.fields.iter_mut().find(|(key, _)| key == name) {
+            Some((_, recorded)) => *recorded = value,
+            None => self.recorded.fields.push((name.to_string(), value)),
+        }
+    }
+}
+
+impl Visit for SpanFieldsVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record(field, format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+}
+
+impl<S> Layer<S> for SpanFieldsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut recorded = SpanFields::default();
+        attrs.record(&mut SpanFieldsVisitor {
+            names: &self.fields,
+            recorded: &mut recorded,
+        });
+        span.extensions_mut().insert(recorded);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(recorded) = extensions.get_mut::<SpanFields>() {
+            values.record(&mut SpanFieldsVisitor {
+                names: &self.fields,
+                recorded,
+            });
+        }
+    }
+}
+
+/// Get the trace ID and span ID of the current span. The trace ID is the
+/// closest recorded `trace_id` field, or else the root span's ID in hex.
+pub(crate) fn current_trace_ids() -> Option<(String, u64)> {
+    ::tracing::dispatcher::get_default(|dispatch| {
+        let current = dispatch.current_span();
+        let id = current.id()?;
+        let Some(span) = dispatch
+            .downcast_ref::<Registry>()
+            .and_then(|registry| registry.span(id))
+        else {
+            // Without a registry the ancestors are unknown
+            return Some((format!("{:016x}", id.into_u64()), id.into_u64()));
+        };
+        let mut root = id.into_u64();
+        for ancestor in span.scope() {
+            if let Some(trace_id) = ancestor
+                .extensions()
+                .get::<SpanFields>()
+                .and_then(|recorded| recorded.trace_id.clone())
+            {
+                return Some((trace_id, id.into_u64()));
+            }
+            root = ancestor.id().into_u64();
+        }
+        Some((format!("{root:016x}"), id.into_u64()))
+    })
+}
+
+/// Get the fields recorded by [`SpanFieldsLayer`] on the current span and its
+/// ancestors, from the outermost span.
+pub(crate) fn current_span_fields() -> Vec<(String, String)> {
+    ::tracing::dispatcher::get_default(|dispatch| {
+        let current = dispatch.current_span();
+        let Some(span) = current.id().and_then(|id| {
+            dispatch
+                .downcast_ref::<Registry>()
+                .and_then(|registry| registry.span(id))
+        }) else {
+            return Vec::new();
+        };
+        let mut fields = Vec::new();
+        for ancestor in span.scope().from_root() {
+            if let Some(recorded) = ancestor.extensions().get::<SpanFields>() {
+                fields.extend(recorded.fields.iter().cloned());
+            }
+        }
+        fields
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::codes::ErrorCode;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_layer_renders_stack_errors() {
+        let buffer = Buffer::default();
+        let layer = StackErrorLayer::new().with_ansi(false).with_writer({
+            let buffer = buffer.clone();
+            move || buffer.clone()
+        });
+        let subscriber = tracing_subscriber::registry().with(layer);
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .with_err_field("path", "config.toml")
+            .stack_err_msg("Stacked error");
+        ::tracing::subscriber::with_default(subscriber, || {
+            ::tracing::info!("no error");
+            ::tracing::error!(error = &error as &dyn std::error::Error, "startup failed");
+        });
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "ERROR startup failed\n  0: Base error\n  1: Stacked error\n  code: IoNotFound\n  path: config.toml\n"
+        );
+    }
+
+    #[test]
+    fn test_errors_capture_span() {
+        let subscriber =
+            tracing_subscriber::registry().with(SpanFieldsLayer::new(["request_id", "step"]));
+        ::tracing::subscriber::with_default(subscriber, || {
+            let error = StackError::from_msg("Outside error");
+            assert_eq!(error.err_trace_id(), None);
+
+            let request = ::tracing::info_span!("request", request_id = "abc", user = "bob");
+            let _request = request.enter();
+            let root_id = format!("{:016x}", request.id().unwrap().into_u64());
+            let step = ::tracing::info_span!("step", step = ::tracing::field::Empty);
+            step.record("step", 2);
+            let _step = step.enter();
+            let error = StackError::from_msg("Base error").stack_err_msg("Stacked error");
+            assert_eq!(error.err_trace_id(), Some(root_id.as_str()));
+            assert_eq!(error.err_span_id(), step.id().map(|id| id.into_u64()));
+            let root = error.into_frames().remove(0);
+            assert_eq!(
+                root.err_fields(),
+                &[
+                    ("request_id".to_string(), "abc".to_string()),
+                    ("step".to_string(), "2".to_string())
+                ]
+            );
+
+            let traced = ::tracing::info_span!("traced", trace_id = "4bf92f3577b34da6");
+            let _traced = traced.enter();
+            let error = StackError::from_msg("Traced error");
+            assert_eq!(error.err_trace_id(), Some("4bf92f3577b34da6"));
+        });
+    }
+
+    #[cfg(feature = "span-trace")]
+    #[test]
+    fn test_errors_capture_span_trace() {
+        let subscriber = tracing_subscriber::registry().with(tracing_error::ErrorLayer::default());
+        let error = ::tracing::subscriber::with_default(subscriber, || {
+            let _request = ::tracing::info_span!("handle_request").entered();
+            let _query = ::tracing::info_span!("run_query").entered();
+            StackError::from_msg("Base error").stack_err_msg("Stacked error")
+        });
+        let span_trace = error.err_span_trace().unwrap().to_string();
+        assert!(span_trace.find("run_query").unwrap() < span_trace.find("handle_request").unwrap());
+        assert!(error
+            .render(crate::render::Verbosity::Full)
+            .contains("span trace:\n"));
+        assert!(StackError::from_msg("Outside error")
+            .err_span_trace()
+            .is_none());
+    }
+}
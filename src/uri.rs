@@ -0,0 +1,112 @@
+//! Provides the [`ErrorUriScheme`] for building error URIs consistently.
+
+use crate::codes::ErrorCode;
+
+/// Builds error URIs from a common base, such as `error://my-crate`.
+///
+/// The `error://` scheme needs no infrastructure, so URIs can be set
+/// consistently before there is a docs site. The base can later be switched
+/// to a docs site with [`ErrorUriScheme::with_base`], without changing the
+/// error names:
+///
+/// ```rust
+/// const URIS: ErrorUriScheme = ErrorUriScheme::new("my-crate");
+/// let error = StackError::not_found("no such user").with_err_uri(URIS.uri("user_not_found"));
+/// // With a docs site: https://docs.example.com/errors/user_not_found
+/// let uris = URIS.with_base("https://docs.example.com/errors");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorUriScheme {
+    crate_name: &'static str,
+    base: Option<String>,
+}
+
+impl ErrorUriScheme {
+    /// Creates a scheme for URIs of the form `error://{crate_name}/{name}`.
+    pub const fn new(crate_name: &'static str) -> Self {
+        Self {
+            crate_name,
+            base: None,
+        }
+    }
+
+    /// Use the given base instead, e.g. `https://docs.example.com/errors`,
+    /// for URIs of the form `{base}/{name}`.
+    pub fn with_base(self, base: impl Into<String>) -> Self {
+        Self {
+            base: Some(base.into()),
+            ..self
+        }
+    }
+
+    /// Get the URI of the error with the given name.
+    pub fn uri(&self, name: &str) -> String {
+        let name = name.trim_start_matches('/');
+        match &self.base {
+            Some(base) => format!("{}/{name}", base.trim_end_matches('/')),
+            None => format!("error://{}/{name}", self.crate_name),
+        }
+    }
+
+    /// Get the URI of an error code, named in snake case, e.g.
+    /// `error://my-crate/http_not_found`.
+    pub fn code_uri(&self, code: ErrorCode) -> String {
+        self.uri(&snake_case(&format!("{code:?}")))
+    }
+}
+
+/// Convert a camel case name to snake case.
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (idx, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if idx > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Builds the URI `error://{crate}/{name}` for the calling crate, as
+/// [`ErrorUriScheme::uri`] with the package name of the crate being built:
+///
+/// ```rust
+/// let error = StackError::conflict("duplicate id").with_err_uri(error_uri!("duplicate_id"));
+/// ```
+#[macro_export]
+macro_rules! error_uri {
+    ($name:expr) => {
+        $crate::uri::ErrorUriScheme::new(env!("CARGO_PKG_NAME")).uri($name)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_scheme() {
+        let uris = ErrorUriScheme::new("my-crate");
+        assert_eq!(
+            uris.uri("user_not_found"),
+            "error://my-crate/user_not_found"
+        );
+        assert_eq!(
+            uris.code_uri(ErrorCode::HttpNotFound),
+            "error://my-crate/http_not_found"
+        );
+        let uris = uris.with_base("https://docs.example.com/errors/");
+        assert_eq!(
+            uris.uri("user_not_found"),
+            "https://docs.example.com/errors/user_not_found"
+        );
+        assert_eq!(
+            error_uri!("user_not_found"),
+            "error://stackerror/user_not_found"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Provides utilities for batch jobs which proceed with the successes of a
+//! batch, and collect the failures for a summary report.
+
+/// Split results into the successful values and the errors, keeping their
+/// order:
+///
+/// ```rust
+/// let (records, errors) = partition_results(lines.map(parse_record));
+/// ```
+pub fn partition_results<T, E>(
+    results: impl IntoIterator<Item = Result<T, E>>,
+) -> (Vec<T>, Vec<E>) {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push(error),
+        }
+    }
+    (values, errors)
+}
+
+/// Split results as [`partition_results`], but stop consuming them once
+/// `threshold` errors are collected, and return the errors. This fails fast
+/// when a batch is mostly failing, e.g. because a dependency is down.
+pub fn partition_results_fail_fast<T, E>(
+    results: impl IntoIterator<Item = Result<T, E>>,
+    threshold: usize,
+) -> Result<(Vec<T>, Vec<E>), Vec<E>> {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push(error),
+        }
+        if errors.len() >= threshold {
+            return Err(errors);
+        }
+    }
+    Ok((values, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn parse(entry: &str) -> StackResult<u32> {
+        entry
+            .parse()
+            .map_err(StackError::from_msg)
+            .stack_err_msg(format!("parsing {entry:?}"))
+    }
+
+    #[test]
+    fn test_partition_results() {
+        let (values, errors) = partition_results(["1", "x", "3"].into_iter().map(parse));
+        assert_eq!(values, [1, 3]);
+        assert_eq!(errors.len(), 1);
+
+        let entries = ["1", "x", "y", "4"];
+        let (values, errors) =
+            partition_results_fail_fast(entries.into_iter().map(parse), 3).unwrap();
+        assert_eq!((values.len(), errors.len()), (2, 2));
+        let errors = partition_results_fail_fast(entries.into_iter().map(parse), 2).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+}
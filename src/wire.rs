@@ -0,0 +1,79 @@
+//! Provides a compact, versioned binary encoding of errors, for embedding in
+//! message payloads.
+//!
+//! An encoded error is a version byte followed by the CBOR encoding of its
+//! [`ErrorPayload`]. The format evolves following these rules:
+//!
+//! - New attributes are only ever added as optional, and decoders ignore
+//!   attributes they don't know, so this doesn't change the version.
+//! - Error codes, hints, origins and severities unknown to the decoder are
+//!   dropped rather than rejected.
+//! - Any other change increments [`WIRE_VERSION`]. Decoders reject versions
+//!   greater than their own, and keep decoding all older versions.
+
+use crate::codes::ErrorCode;
+use crate::error::{ErrorStacks, StackError};
+use crate::payload::ErrorPayload;
+
+/// The current version of the wire format.
+pub const WIRE_VERSION: u8 = 1;
+
+/// Encode an error in the wire format.
+pub fn to_wire(error: &StackError) -> Vec<u8> {
+    let mut bytes = vec![WIRE_VERSION];
+    ciborium::into_writer(&ErrorPayload::from(error), &mut bytes)
+        .expect("writing to a vector can't fail");
+    bytes
+}
+
+/// Decode an error from the wire format.
+///
+/// Returns an error with the [`ErrorCode::IoInvalidData`] code if the
+/// version isn't supported or the payload is malformed.
+pub fn from_wire(bytes: &[u8]) -> Result<StackError, StackError> {
+    let Some((&version, payload)) = bytes.split_first() else {
+        return Err(
+            StackError::from_msg("empty wire payload").with_err_code(ErrorCode::IoInvalidData)
+        );
+    };
+    if version == 0 || version > WIRE_VERSION {
+        return Err(
+            StackError::from_msg(format!("unsupported wire version {version}"))
+                .with_err_code(ErrorCode::IoInvalidData),
+        );
+    }
+    ciborium::from_reader::<ErrorPayload, _>(payload)
+        .map(StackError::from)
+        .map_err(|e| {
+            StackError::from_msg(e)
+                .with_err_code(ErrorCode::IoInvalidData)
+                .stack_err_msg("invalid wire payload")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_round_trips() {
+        let error = StackError::from_msg("Base error")
+            .with_err_code(ErrorCode::IoNotFound)
+            .stack_err_msg("Stacked error");
+        let bytes = to_wire(&error);
+        assert_eq!(bytes[0], WIRE_VERSION);
+        let decoded = from_wire(&bytes).unwrap();
+        assert_eq!(format!("{:?}", decoded), "Base error\nStacked error");
+        assert_eq!(decoded.err_code(), Some(&ErrorCode::IoNotFound));
+        assert!(decoded.err_location().is_none());
+    }
+
+    #[test]
+    fn test_wire_rejects_newer_version() {
+        let mut bytes = to_wire(&StackError::from_msg("Base error"));
+        bytes[0] = WIRE_VERSION + 1;
+        let err = from_wire(&bytes).unwrap_err();
+        assert_eq!(err.err_code(), Some(&ErrorCode::IoInvalidData));
+        assert!(from_wire(&[]).is_err());
+    }
+}
@@ -0,0 +1,28 @@
+//! Tests of the behaviour specific to `wasm32-unknown-unknown`. They are run
+//! with `wasm-bindgen-test-runner` as the target runner:
+//!
+//! ```sh
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test --target wasm32-unknown-unknown --features js
+//! ```
+
+#![cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+
+use stackerror::prelude::*;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_error_stacks() {
+    let error = StackError::from_msg("Base error")
+        .with_err_code(ErrorCode::IoNotFound)
+        .stack_err_msg("Stacked error");
+    assert_eq!(format!("{:?}", error), "Base error\nStacked error");
+    assert_eq!(error.err_code(), Some(&ErrorCode::IoNotFound));
+}
+
+#[wasm_bindgen_test]
+fn test_error_timestamp() {
+    let error = StackError::from_msg("Base error");
+    assert_eq!(error.timestamp().is_some(), cfg!(feature = "js"));
+    assert!(error.render_verbose().starts_with("0: Base error\n"));
+}